@@ -0,0 +1,2 @@
+-- Odstranitev stolpca z vrsto poverilnice
+ALTER TABLE punches DROP COLUMN credential_type;
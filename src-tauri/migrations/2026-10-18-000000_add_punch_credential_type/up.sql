@@ -0,0 +1,2 @@
+-- Vrsta poverilnice (npr. mifare_classic_1k, em4100), s katero je bil zabeležen dogodek
+ALTER TABLE punches ADD COLUMN credential_type TEXT;
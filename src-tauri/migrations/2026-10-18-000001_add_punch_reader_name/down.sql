@@ -0,0 +1,2 @@
+-- Odstranitev stolpca z imenom čitalnika
+ALTER TABLE punches DROP COLUMN reader_name;
@@ -0,0 +1,2 @@
+-- Ime čitalnika, na katerem je bil zabeležen dogodek
+ALTER TABLE punches ADD COLUMN reader_name TEXT;
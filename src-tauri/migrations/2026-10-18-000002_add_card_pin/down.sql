@@ -0,0 +1,4 @@
+-- Odstranitev stolpcev za kodo PIN
+ALTER TABLE cards DROP COLUMN pin_locked_until;
+ALTER TABLE cards DROP COLUMN pin_failures;
+ALTER TABLE cards DROP COLUMN pin_hash;
@@ -0,0 +1,4 @@
+-- Zgoščena koda PIN zaposlenega in zaklep po ponavljajočih se napačnih vnosih
+ALTER TABLE cards ADD COLUMN pin_hash TEXT;
+ALTER TABLE cards ADD COLUMN pin_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE cards ADD COLUMN pin_locked_until TIMESTAMP;
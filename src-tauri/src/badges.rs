@@ -0,0 +1,78 @@
+use diesel::prelude::*;
+
+use crate::models::{Card, NewCard};
+
+/// Translation key returned when a badge carries an employee number while its UID is still
+/// assigned to another employee.
+pub const BADGE_ASSIGNED_ERROR: &str = "This badge is already assigned to another employee";
+
+enum LinkError {
+    Assigned,
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for LinkError {
+    fn from(error: diesel::result::Error) -> Self {
+        LinkError::Database(error)
+    }
+}
+
+/// Points the card row of `employee_id` at the badge just read, so a reprinted badge keeps
+/// its owner and punch history. A badge seen for the first time is enrolled.
+///
+/// A row that already holds the badge without an owner, such as one enrolled by an earlier
+/// tap, is merged into the employee's row together with its punches. A badge still assigned
+/// to another employee is refused, so their history does not move to the new holder.
+pub fn link_badge(conn: &mut SqliteConnection, employee_id: &str, uid_hex: &str) -> Result<(), String> {
+    conn.transaction(|conn| link(conn, employee_id, uid_hex))
+        .map_err(|e| match e {
+            LinkError::Assigned => BADGE_ASSIGNED_ERROR.to_string(),
+            LinkError::Database(e) => format!("Update error: {}", e),
+        })
+}
+
+fn link(conn: &mut SqliteConnection, employee_id: &str, uid_hex: &str) -> Result<(), LinkError> {
+    use crate::schema::cards::dsl::*;
+    use crate::schema::punches::dsl as punches_dsl;
+    let owner = cards.filter(user_id.eq(employee_id)).first::<Card>(conn).optional()?;
+    let holder = cards.filter(card_number.eq(uid_hex)).first::<Card>(conn).optional()?;
+    if let Some(ref holder) = holder {
+        if holder.user_id.as_deref().is_some_and(|other| other != employee_id) {
+            println!("Badge {} of employee {:?} presented as employee {}", uid_hex, holder.user_id, employee_id);
+            return Err(LinkError::Assigned);
+        }
+    }
+    match (owner, holder) {
+        (Some(owner), Some(holder)) if owner.id == holder.id => {}
+        (Some(owner), Some(holder)) => {
+            println!("Merging card {} into the row of employee {}", uid_hex, employee_id);
+            diesel::update(punches_dsl::punches.filter(punches_dsl::card_id.eq(holder.id)))
+                .set(punches_dsl::card_id.eq(owner.id))
+                .execute(conn)?;
+            diesel::delete(cards.filter(id.eq(holder.id))).execute(conn)?;
+            diesel::update(cards.filter(id.eq(owner.id)))
+                .set(card_number.eq(uid_hex))
+                .execute(conn)?;
+        }
+        (Some(owner), None) => {
+            println!("Badge of employee {} changed to {}", employee_id, uid_hex);
+            diesel::update(cards.filter(id.eq(owner.id)))
+                .set(card_number.eq(uid_hex))
+                .execute(conn)?;
+        }
+        (None, Some(holder)) => {
+            diesel::update(cards.filter(id.eq(holder.id)))
+                .set(user_id.eq(employee_id))
+                .execute(conn)?;
+        }
+        (None, None) => {
+            let new_card = NewCard {
+                card_number: uid_hex,
+                card_name: None,
+                user_id: Some(employee_id),
+            };
+            diesel::insert_into(cards).values(&new_card).execute(conn)?;
+        }
+    }
+    Ok(())
+}
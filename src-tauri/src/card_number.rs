@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::reader::card::parse_hex;
+use crate::reader::hid::WedgeFormat;
+
+/// A way of writing a card's UID. Cards are stored as [`CardNumberFormat::Hex`]; the
+/// others are what badge prints and third-party systems show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardNumberFormat {
+    /// Colon-separated lowercase hex, e.g. "04:a1:b2:c3".
+    Hex,
+    /// The UID as one big-endian number, padded to 10 digits, e.g. "0077705923".
+    Decimal,
+    /// The same with the bytes reversed, as USB desktop readers type it.
+    ReversedDecimal,
+    /// Facility code and card number of a Wiegand UID, e.g. "123/45678".
+    Wiegand,
+}
+
+impl CardNumberFormat {
+    pub const ALL: [CardNumberFormat; 4] = [
+        CardNumberFormat::Hex,
+        CardNumberFormat::Decimal,
+        CardNumberFormat::ReversedDecimal,
+        CardNumberFormat::Wiegand,
+    ];
+
+    /// Writes `uid` in this format, or `None` when the format cannot hold it.
+    pub fn format(&self, uid: &[u8]) -> Option<String> {
+        if uid.is_empty() {
+            return None;
+        }
+        match self {
+            CardNumberFormat::Hex => Some(uid.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")),
+            CardNumberFormat::Decimal => decimal(uid.iter().copied()),
+            CardNumberFormat::ReversedDecimal => decimal(uid.iter().rev().copied()),
+            CardNumberFormat::Wiegand => {
+                // 26-bit frames carry an 8-bit facility code, 34-bit frames a 16-bit one.
+                if !(3..=4).contains(&uid.len()) {
+                    return None;
+                }
+                let (facility, number) = uid.split_at(uid.len() - 2);
+                let facility = facility.iter().fold(0u32, |value, b| value << 8 | *b as u32);
+                Some(format!("{}/{}", facility, u16::from_be_bytes([number[0], number[1]])))
+            }
+        }
+    }
+
+    /// Reads a number written in this format back into a UID.
+    pub fn parse(&self, text: &str) -> Option<Vec<u8>> {
+        let text = text.trim();
+        match self {
+            CardNumberFormat::Hex => parse_hex(text).filter(|uid| !uid.is_empty()),
+            CardNumberFormat::Decimal => WedgeFormat::Decimal.parse(text),
+            CardNumberFormat::ReversedDecimal => {
+                let mut uid = WedgeFormat::Decimal.parse(text)?;
+                uid.reverse();
+                Some(uid)
+            }
+            CardNumberFormat::Wiegand => {
+                let (facility, number) = text.split_once(['/', ','])?;
+                let facility: u16 = facility.trim().parse().ok()?;
+                let number: u16 = number.trim().parse().ok()?;
+                let mut uid = match u8::try_from(facility) {
+                    Ok(facility) => vec![facility],
+                    Err(_) => facility.to_be_bytes().to_vec(),
+                };
+                uid.extend_from_slice(&number.to_be_bytes());
+                Some(uid)
+            }
+        }
+    }
+
+    /// Every UID a number written in this format may stand for, [`parse`](Self::parse)'s
+    /// reading first. Decimal and Wiegand numbers do not say how wide the UID was, so one
+    /// that fits in 3 bytes is also read as 4 and the other way round: a 26-bit card is
+    /// stored in 3 bytes, a 34-bit one with a facility code below 256 in 4.
+    pub fn readings(&self, text: &str) -> Vec<Vec<u8>> {
+        let Some(uid) = self.parse(text) else {
+            return Vec::new();
+        };
+        let other = match self {
+            CardNumberFormat::Hex => None,
+            CardNumberFormat::Decimal | CardNumberFormat::Wiegand => other_width(&uid),
+            CardNumberFormat::ReversedDecimal => {
+                let reversed: Vec<u8> = uid.iter().rev().copied().collect();
+                other_width(&reversed).map(|other| other.into_iter().rev().collect())
+            }
+        };
+        std::iter::once(uid).chain(other).collect()
+    }
+}
+
+/// The 4-byte form of a 3-byte UID, or the 3-byte form of a 4-byte one with a zero lead.
+fn other_width(uid: &[u8]) -> Option<Vec<u8>> {
+    match uid {
+        [_, _, _] => Some([&[0], uid].concat()),
+        [0, rest @ ..] if rest.len() == 3 => Some(rest.to_vec()),
+        _ => None,
+    }
+}
+
+fn decimal(bytes: impl Iterator<Item = u8>) -> Option<String> {
+    let mut value: u64 = 0;
+    for (index, byte) in bytes.enumerate() {
+        if index >= 8 {
+            return None;
+        }
+        value = value << 8 | byte as u64;
+    }
+    Some(format!("{:010}", value))
+}
+
+/// A card number written in one format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FormattedNumber {
+    pub format: CardNumberFormat,
+    pub number: String,
+}
+
+/// Every way `uid` can be written in `formats`, in that order.
+pub fn representations(uid: &[u8], formats: &[CardNumberFormat]) -> Vec<FormattedNumber> {
+    formats
+        .iter()
+        .filter_map(|format| {
+            format.format(uid).map(|number| FormattedNumber {
+                format: *format,
+                number,
+            })
+        })
+        .collect()
+}
+
+/// The UIDs `text` may stand for, trying `formats` in order. A number can be ambiguous,
+/// e.g. "12345678" is valid hex and valid decimal, so callers look each one up in turn.
+pub fn candidates(text: &str, formats: &[CardNumberFormat]) -> Vec<Vec<u8>> {
+    let mut uids: Vec<Vec<u8>> = Vec::new();
+    for uid in formats.iter().flat_map(|format| format.readings(text)) {
+        if !uids.contains(&uid) {
+            uids.push(uid);
+        }
+    }
+    uids
+}
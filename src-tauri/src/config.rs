@@ -0,0 +1,548 @@
+use chrono::NaiveDateTime;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::card_number::{self, CardNumberFormat, FormattedNumber};
+use crate::feedback::cdev::CdevGpio;
+use crate::feedback::{GpioBackend, Outcome, Player, Pn532Gpio, Pn532GpioWriter, Pulse};
+use crate::reader::barcode::{BarcodeReader, QrCredential, DEFAULT_SCANNER_BAUD_RATE};
+use crate::reader::desfire::DesfireCredential;
+use crate::reader::hce::PhoneCredential;
+use crate::reader::hid::{HidWedgeReader, WedgeFormat};
+use crate::reader::pn532::autopoll::ScanMode;
+use crate::reader::pn532::classic::{ClassicCredential, KeyType};
+use crate::reader::pn532::transport::TransportKind;
+use crate::reader::pn532::{Pn532Reader, DEFAULT_SPI_SPEED_HZ, DEFAULT_UART_BAUD_RATE};
+use crate::reader::rdm6300::Rdm6300;
+use crate::reader::simulated::{SimulatedReader, DEFAULT_SOCKET_PATH};
+use crate::reader::wiegand::WiegandReader;
+use crate::reader::{CardFamily, DetectedCard, Reader, ReaderError, ReaderType, TargetType};
+
+const CONFIG_FILE_NAME: &str = "terminal.json";
+
+/// Terminal configuration, read once from `terminal.json` next to the database.
+///
+/// A missing file yields the defaults, so a bare install keeps using the PN532 on I2C.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TerminalConfig {
+    pub reader: ReaderConfig,
+    /// Named readers polled side by side. When empty, `reader` is the only one, named
+    /// "default" and toggling between in and out.
+    pub readers: Vec<NamedReaderConfig>,
+    pub credential: CredentialConfig,
+    pub originality: OriginalityPolicy,
+    /// Lets phones running the terminal app punch, next to the configured badges.
+    pub phone: Option<PhoneConfig>,
+    /// Lets temporary workers punch with printed QR codes on a barcode scanner.
+    pub qr: Option<QrConfig>,
+    pub anti_passback: AntiPassbackPolicy,
+    /// Buzzer and LEDs signalling the outcome of a punch; off when absent.
+    pub feedback: Option<FeedbackConfig>,
+    pub card_numbers: CardNumberConfig,
+    pub pin: PinPolicy,
+}
+
+impl TerminalConfig {
+    /// The readers to poll, falling back to the single `reader` of older configurations.
+    pub fn reader_configs(&self) -> Vec<NamedReaderConfig> {
+        if !self.readers.is_empty() {
+            return self.readers.clone();
+        }
+        vec![NamedReaderConfig {
+            name: String::from(DEFAULT_READER_NAME),
+            role: ReaderRole::Toggle,
+            reader: self.reader.clone(),
+        }]
+    }
+}
+
+pub const DEFAULT_READER_NAME: &str = "default";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NamedReaderConfig {
+    pub name: String,
+    #[serde(default)]
+    pub role: ReaderRole,
+    #[serde(flatten)]
+    pub reader: ReaderConfig,
+}
+
+/// Which punch a tap on a reader records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReaderRole {
+    /// Always punches in, e.g. at the entry turnstile.
+    Entry,
+    /// Always punches out.
+    Exit,
+    /// Punches in unless the card is already in.
+    #[default]
+    Toggle,
+    /// Only registers the leave chosen on the screen.
+    LeaveOnly,
+}
+
+/// Minimum time between two punches of the same card. A card resting on the reader is
+/// already reported only once, so this only guards against deliberate re-taps.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AntiPassbackPolicy {
+    pub min_interval_secs: i64,
+}
+
+impl Default for AntiPassbackPolicy {
+    fn default() -> Self {
+        Self { min_interval_secs: 1 }
+    }
+}
+
+impl AntiPassbackPolicy {
+    /// Whether a punch at `now` may follow one recorded at `last`.
+    pub fn allows(&self, last: NaiveDateTime, now: NaiveDateTime) -> bool {
+        now.signed_duration_since(last).num_seconds() > self.min_interval_secs
+    }
+}
+
+/// Lockout of PIN punches after repeated wrong PINs for the same employee.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PinPolicy {
+    pub max_failures: i32,
+    pub lockout_secs: i64,
+}
+
+impl Default for PinPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            lockout_secs: 300,
+        }
+    }
+}
+
+impl PinPolicy {
+    pub fn is_locked(&self, locked_until: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+        locked_until.is_some_and(|until| now < until)
+    }
+
+    /// The failure count and lock to store after a wrong PIN. Reaching `max_failures`
+    /// locks the employee out and starts the count over.
+    pub fn after_failure(&self, failures: i32, now: NaiveDateTime) -> (i32, Option<NaiveDateTime>) {
+        let failures = failures + 1;
+        if failures >= self.max_failures {
+            (0, Some(now + chrono::Duration::seconds(self.lockout_secs)))
+        } else {
+            (failures, None)
+        }
+    }
+}
+
+/// How card numbers are shown to admins and which written forms a typed-in number is
+/// tried as. Cards are always stored in hex, the form the readers report.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CardNumberConfig {
+    /// The form shown as a card's number, e.g. the one printed on the badges.
+    pub display: CardNumberFormat,
+    /// Forms to try, in order, when a number could be read several ways.
+    pub accept: Vec<CardNumberFormat>,
+}
+
+impl Default for CardNumberConfig {
+    fn default() -> Self {
+        Self {
+            display: CardNumberFormat::Hex,
+            accept: CardNumberFormat::ALL.to_vec(),
+        }
+    }
+}
+
+impl CardNumberConfig {
+    /// The accepted formats, with hex always among them since the frontend sends it.
+    fn formats(&self) -> Vec<CardNumberFormat> {
+        let mut formats = self.accept.clone();
+        if !formats.contains(&CardNumberFormat::Hex) {
+            formats.push(CardNumberFormat::Hex);
+        }
+        formats
+    }
+
+    /// `uid` in the display format first, then in every other accepted one.
+    pub fn representations(&self, uid: &[u8]) -> Vec<FormattedNumber> {
+        let mut formats = vec![self.display];
+        formats.extend(self.formats().into_iter().filter(|format| *format != self.display));
+        card_number::representations(uid, &formats)
+    }
+
+    /// The UIDs a typed-in number may stand for, most likely first.
+    pub fn candidates(&self, text: &str) -> Vec<Vec<u8>> {
+        card_number::candidates(text, &self.formats())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedbackConfig {
+    pub backend: GpioBackendConfig,
+    /// Output names used by the patterns, mapped to P3 pin numbers (0-5) for the PN532 or
+    /// line offsets for a GPIO chip.
+    pub lines: HashMap<String, u32>,
+    /// Lines are active when driven low, as with LEDs wired to the supply.
+    #[serde(default)]
+    pub active_low: bool,
+    /// Overrides of the built-in pattern of an outcome.
+    #[serde(default)]
+    pub patterns: HashMap<Outcome, Vec<Pulse>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GpioBackendConfig {
+    /// The P3x pins of a PN532 the terminal reads cards with.
+    Pn532 {
+        /// Name of the reader whose pins are driven; the first reader when absent.
+        #[serde(default)]
+        reader: Option<String>,
+    },
+    /// A Linux GPIO character device.
+    Cdev {
+        #[serde(default = "default_gpio_chip")]
+        chip: String,
+    },
+}
+
+fn default_gpio_chip() -> String {
+    String::from("/dev/gpiochip0")
+}
+
+impl FeedbackConfig {
+    /// Name of the reader whose P3 pins the PN532 backend drives, if one was chosen.
+    pub fn pn532_reader(&self) -> Option<&str> {
+        match &self.backend {
+            GpioBackendConfig::Pn532 { reader } => reader.as_deref(),
+            GpioBackendConfig::Cdev { .. } => None,
+        }
+    }
+
+    /// Opens the configured backend. `pn532` sends WriteGPIO through the chosen reader.
+    pub fn open(&self, pn532: Pn532GpioWriter) -> Result<Player, ReaderError> {
+        let mut lines: Vec<u32> = self.lines.values().copied().collect();
+        lines.sort_unstable();
+        lines.dedup();
+        let backend: Box<dyn GpioBackend> = match &self.backend {
+            GpioBackendConfig::Pn532 { .. } => Box::new(Pn532Gpio::new(&lines, self.active_low, pn532)?),
+            GpioBackendConfig::Cdev { chip } => Box::new(CdevGpio::open(chip, &lines, self.active_low)?),
+        };
+        Ok(Player::new(backend, self.lines.clone(), self.patterns.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhoneConfig {
+    /// AID of the terminal application the phone emulates, in hex.
+    pub aid: String,
+    /// Master secret in hex. Each employee's app signs with a key derived from it, see
+    /// [`PhoneCredential::employee_key`].
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QrConfig {
+    /// Secret the QR codes are signed with, in hex.
+    pub key: String,
+}
+
+impl QrConfig {
+    pub fn credential(&self) -> Result<QrCredential, ReaderError> {
+        QrCredential::new(&self.key)
+    }
+}
+
+/// How a barcode scanner is attached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeInterface {
+    #[default]
+    Serial,
+    /// Keyboard mode, read from the scanner's evdev node.
+    Hid,
+}
+
+/// What to do with NTAG/Ultralight tags whose NXP originality signature does not verify.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OriginalityPolicy {
+    /// The signature is not read.
+    #[default]
+    Off,
+    /// Failures are logged, but the tag can still punch.
+    Warn,
+    /// Only cards that prove they are genuine can punch, see [`OriginalityPolicy::admits`].
+    Require,
+}
+
+impl OriginalityPolicy {
+    /// Whether `card` may punch under this policy.
+    ///
+    /// `require` admits NTAG/Ultralight tags read by a PN532 whose signature verified, and
+    /// credentials that are signed or authenticated on their own: phone tokens, AES-read
+    /// DESFire applications and QR codes. Everything else is refused, including cards from
+    /// readers that cannot check a signature.
+    pub fn admits(&self, card: &DetectedCard) -> bool {
+        if *self != OriginalityPolicy::Require {
+            return true;
+        }
+        match card.family {
+            CardFamily::MifareUltralight => card.reader_type == ReaderType::NFC && card.signature_valid == Some(true),
+            CardFamily::IsoDep | CardFamily::Desfire | CardFamily::QrCode => card.credential.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// What identifies a person on their badge.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CredentialConfig {
+    /// The hex UID is the card number.
+    #[default]
+    Uid,
+    /// The employee number is read from a MIFARE Classic data block and matched against the
+    /// card's `user_id`, so reprinted badges with a new UID keep working.
+    MifareClassic {
+        block: u8,
+        #[serde(default)]
+        key_type: KeyType,
+        /// Six-byte key in hex, e.g. "FFFFFFFFFFFF".
+        key: String,
+    },
+    /// The employee number is the first text (or URI) record of the NDEF message on
+    /// NTAG/Ultralight stickers; tags without a message are identified by their UID.
+    Ndef,
+    /// The employee number is read from an encrypted file of a DESFire EV2/EV3 application
+    /// after AES authentication, so a card with a copied UID cannot punch.
+    Desfire {
+        /// Application ID, most significant byte first ("F54210").
+        aid: String,
+        #[serde(default)]
+        key_no: u8,
+        /// 16-byte AES key in hex.
+        key: String,
+        file_no: u8,
+        #[serde(default)]
+        offset: u32,
+        length: u32,
+    },
+}
+
+impl CredentialConfig {
+    /// Whether a card without an employee number is refused instead of identified by UID.
+    pub fn requires_employee_id(&self) -> bool {
+        matches!(
+            self,
+            CredentialConfig::MifareClassic { .. } | CredentialConfig::Desfire { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ReaderConfig {
+    Pn532 {
+        #[serde(default = "default_pn532_transport")]
+        transport: TransportKind,
+        #[serde(default)]
+        path: Option<String>,
+        /// UART baud rate, or SPI clock in Hz.
+        #[serde(default)]
+        speed: Option<u32>,
+        #[serde(default)]
+        scan_mode: ScanMode,
+        /// Target types InAutoPoll looks for; empty means type A, FeliCa and type B.
+        #[serde(default)]
+        poll_types: Vec<TargetType>,
+    },
+    Rdm6300 {
+        #[serde(default)]
+        path: Option<String>,
+    },
+    /// USB reader typing the card number like a keyboard, read from its evdev node.
+    Hid {
+        /// `/dev/input/event*`, preferably the stable `/dev/input/by-id/` link.
+        path: String,
+        #[serde(default)]
+        format: WedgeFormat,
+    },
+    /// Door reader sending Wiegand 26/34 on two GPIO lines.
+    Wiegand {
+        #[serde(default = "default_gpio_chip")]
+        chip: String,
+        /// Line offsets of D0 and D1 on the chip.
+        d0: u32,
+        d1: u32,
+    },
+    /// Barcode scanner reading the signed QR codes of temporary workers.
+    Barcode {
+        path: String,
+        #[serde(default)]
+        interface: BarcodeInterface,
+        /// Serial baud rate; 9600 when absent.
+        #[serde(default)]
+        speed: Option<u32>,
+    },
+    /// Reader without hardware, tapped through a Unix socket. For development and tests.
+    Simulated {
+        #[serde(default = "default_simulated_path")]
+        path: String,
+    },
+}
+
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        ReaderConfig::Pn532 {
+            transport: TransportKind::I2c,
+            path: None,
+            speed: None,
+            scan_mode: ScanMode::default(),
+            poll_types: Vec::new(),
+        }
+    }
+}
+
+impl ReaderConfig {
+    pub fn reader_type(&self) -> ReaderType {
+        match self {
+            ReaderConfig::Pn532 { .. } => ReaderType::NFC,
+            ReaderConfig::Rdm6300 { .. } | ReaderConfig::Hid { .. } => ReaderType::RFID,
+            ReaderConfig::Wiegand { .. } => ReaderType::Wiegand,
+            ReaderConfig::Simulated { .. } => ReaderType::Simulated,
+            ReaderConfig::Barcode { .. } => ReaderType::Barcode,
+        }
+    }
+
+    /// The configured device path, if one was set explicitly.
+    pub fn path(&self) -> Option<String> {
+        match self {
+            ReaderConfig::Pn532 { path, .. } | ReaderConfig::Rdm6300 { path } => path.clone(),
+            ReaderConfig::Hid { path, .. } | ReaderConfig::Simulated { path } | ReaderConfig::Barcode { path, .. } => {
+                Some(path.clone())
+            }
+            ReaderConfig::Wiegand { chip, .. } => Some(chip.clone()),
+        }
+    }
+
+    pub fn transport_name(&self) -> &'static str {
+        match self {
+            ReaderConfig::Pn532 { transport, .. } => transport.as_str(),
+            ReaderConfig::Rdm6300 { .. } => "uart",
+            ReaderConfig::Hid { .. } => "evdev",
+            ReaderConfig::Wiegand { .. } => "gpio",
+            ReaderConfig::Simulated { .. } => "unix",
+            ReaderConfig::Barcode { interface: BarcodeInterface::Serial, .. } => "uart",
+            ReaderConfig::Barcode { interface: BarcodeInterface::Hid, .. } => "evdev",
+        }
+    }
+
+    /// Builds the configured reader without initialising it.
+    pub fn open(&self, terminal: &TerminalConfig) -> Result<Box<dyn Reader>, ReaderError> {
+        match self {
+            ReaderConfig::Pn532 {
+                transport,
+                path,
+                speed,
+                scan_mode,
+                poll_types,
+            } => {
+                let reader = match (transport, path) {
+                    (TransportKind::I2c, Some(path)) => Pn532Reader::i2c(path.clone())?,
+                    (TransportKind::I2c, None) => Pn532Reader::with_default_path()?,
+                    (TransportKind::Uart, path) => Pn532Reader::uart(
+                        path.as_deref().unwrap_or("/dev/ttyS0"),
+                        speed.unwrap_or(DEFAULT_UART_BAUD_RATE),
+                    )?,
+                    (TransportKind::Spi, path) => Pn532Reader::spi(
+                        path.clone().unwrap_or_else(|| String::from("/dev/spidev0.0")),
+                        speed.unwrap_or(DEFAULT_SPI_SPEED_HZ),
+                    )?,
+                };
+                let reader = match scan_mode {
+                    ScanMode::ListPassiveTarget => reader,
+                    ScanMode::AutoPoll => reader.with_auto_poll(poll_types.clone()),
+                };
+                let reader = match terminal.originality {
+                    OriginalityPolicy::Off => reader,
+                    _ => reader.with_originality_check(),
+                };
+                let reader = match &terminal.phone {
+                    Some(phone) => reader.with_phone_credential(PhoneCredential::new(&phone.aid, &phone.key)?),
+                    None => reader,
+                };
+                Ok(match &terminal.credential {
+                    CredentialConfig::Uid => Box::new(reader),
+                    CredentialConfig::MifareClassic { block, key_type, key } => {
+                        Box::new(reader.with_classic_credential(ClassicCredential::new(*block, *key_type, key)?))
+                    }
+                    CredentialConfig::Ndef => Box::new(reader.with_ndef_credential()),
+                    CredentialConfig::Desfire {
+                        aid,
+                        key_no,
+                        key,
+                        file_no,
+                        offset,
+                        length,
+                    } => Box::new(reader.with_desfire_credential(DesfireCredential::new(
+                        aid, *key_no, key, *file_no, *offset, *length,
+                    )?)),
+                })
+            }
+            ReaderConfig::Rdm6300 { path: Some(path) } => Ok(Box::new(Rdm6300::new(path.clone()))),
+            ReaderConfig::Rdm6300 { path: None } => Ok(Box::new(Rdm6300::with_default_path())),
+            ReaderConfig::Hid { path, format } => Ok(Box::new(HidWedgeReader::new(path.clone(), *format))),
+            ReaderConfig::Wiegand { chip, d0, d1 } => Ok(Box::new(WiegandReader::gpio(chip.clone(), *d0, *d1))),
+            ReaderConfig::Simulated { path } => Ok(Box::new(SimulatedReader::new(path))),
+            ReaderConfig::Barcode { path, interface, speed } => {
+                let credential = terminal
+                    .qr
+                    .as_ref()
+                    .ok_or_else(|| ReaderError::Initialization("A barcode scanner needs the \"qr\" key configured".into()))?
+                    .credential()?;
+                Ok(Box::new(match interface {
+                    BarcodeInterface::Serial => BarcodeReader::serial(
+                        path.clone(),
+                        speed.unwrap_or(DEFAULT_SCANNER_BAUD_RATE),
+                        credential,
+                    ),
+                    BarcodeInterface::Hid => BarcodeReader::hid(path.clone(), credential),
+                }))
+            }
+        }
+    }
+}
+
+fn default_pn532_transport() -> TransportKind {
+    TransportKind::I2c
+}
+
+fn default_simulated_path() -> String {
+    String::from(DEFAULT_SOCKET_PATH)
+}
+
+/// The configuration in [`config_path`]. Startup checks it with [`read_config`] first, so a
+/// broken file stops the terminal there instead of here.
+pub static CONFIG: Lazy<TerminalConfig> = Lazy::new(|| read_config(&config_path()).unwrap_or_else(|e| panic!("{}", e)));
+
+pub fn config_path() -> PathBuf {
+    crate::app_dir().join(CONFIG_FILE_NAME)
+}
+
+/// Reads the configuration at `path`. Only a missing file yields the defaults: one that
+/// cannot be read or parsed is an error, as the defaults would silently drop its policies.
+pub fn read_config(path: &Path) -> Result<TerminalConfig, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(TerminalConfig::default()),
+        Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
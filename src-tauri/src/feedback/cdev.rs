@@ -0,0 +1,114 @@
+use libc::c_ulong;
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+
+use super::GpioBackend;
+use crate::reader::ReaderError;
+
+const GPIOHANDLES_MAX: usize = 64;
+const GPIOHANDLE_REQUEST_OUTPUT: u32 = 1 << 1;
+const GPIOHANDLE_REQUEST_ACTIVE_LOW: u32 = 1 << 2;
+/// _IOWR(0xB4, 0x03, struct gpiohandle_request)
+const GPIO_GET_LINEHANDLE_IOCTL: c_ulong = 0xC16C_B403;
+/// _IOWR(0xB4, 0x09, struct gpiohandle_data)
+const GPIOHANDLE_SET_LINE_VALUES_IOCTL: c_ulong = 0xC040_B409;
+const CONSUMER_LABEL: &[u8] = b"urniknet-terminal";
+
+#[repr(C)]
+struct GpioHandleRequest {
+    line_offsets: [u32; GPIOHANDLES_MAX],
+    flags: u32,
+    default_values: [u8; GPIOHANDLES_MAX],
+    consumer_label: [u8; 32],
+    lines: u32,
+    fd: libc::c_int,
+}
+
+#[repr(C)]
+struct GpioHandleData {
+    values: [u8; GPIOHANDLES_MAX],
+}
+
+/// Output lines of a Linux GPIO character device (`/dev/gpiochipN`), including the
+/// simulated chips of the `gpio-sim` module.
+///
+/// All lines are requested as one handle when opened and start idle; the kernel releases
+/// them when the handle is dropped.
+pub struct CdevGpio {
+    handle: File,
+    offsets: Vec<u32>,
+    values: [u8; GPIOHANDLES_MAX],
+}
+
+impl CdevGpio {
+    pub fn open(chip: &str, offsets: &[u32], active_low: bool) -> Result<Self, ReaderError> {
+        if offsets.is_empty() || offsets.len() > GPIOHANDLES_MAX {
+            return Err(ReaderError::Initialization(format!(
+                "Between 1 and {} GPIO lines can be requested, got {}",
+                GPIOHANDLES_MAX,
+                offsets.len()
+            )));
+        }
+        let chip_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(chip)
+            .map_err(|e| ReaderError::Initialization(format!("{}: {}", chip, e)))?;
+
+        let mut request = GpioHandleRequest {
+            line_offsets: [0; GPIOHANDLES_MAX],
+            flags: GPIOHANDLE_REQUEST_OUTPUT,
+            default_values: [0; GPIOHANDLES_MAX],
+            consumer_label: [0; 32],
+            lines: offsets.len() as u32,
+            fd: -1,
+        };
+        request.line_offsets[..offsets.len()].copy_from_slice(offsets);
+        if active_low {
+            request.flags |= GPIOHANDLE_REQUEST_ACTIVE_LOW;
+        }
+        request.consumer_label[..CONSUMER_LABEL.len()].copy_from_slice(CONSUMER_LABEL);
+
+        gpio_ioctl(
+            chip_file.as_raw_fd(),
+            GPIO_GET_LINEHANDLE_IOCTL,
+            &mut request as *mut GpioHandleRequest as *mut libc::c_void,
+        )
+        .map_err(|e| ReaderError::Initialization(format!("Failed to request GPIO lines on {}: {}", chip, e)))?;
+
+        Ok(Self {
+            // SAFETY: the kernel handed us a fresh descriptor that nothing else owns.
+            handle: unsafe { File::from_raw_fd(request.fd) },
+            offsets: offsets.to_vec(),
+            values: [0; GPIOHANDLES_MAX],
+        })
+    }
+}
+
+impl GpioBackend for CdevGpio {
+    fn set(&mut self, lines: &[u32], active: bool) -> Result<(), ReaderError> {
+        for line in lines {
+            let index = self
+                .offsets
+                .iter()
+                .position(|offset| offset == line)
+                .ok_or_else(|| ReaderError::Device(format!("GPIO line {} was not requested", line)))?;
+            self.values[index] = active as u8;
+        }
+        let mut data = GpioHandleData { values: self.values };
+        gpio_ioctl(
+            self.handle.as_raw_fd(),
+            GPIOHANDLE_SET_LINE_VALUES_IOCTL,
+            &mut data as *mut GpioHandleData as *mut libc::c_void,
+        )
+        .map_err(|e| ReaderError::Device(format!("Failed to set GPIO lines: {}", e)))
+    }
+}
+
+fn gpio_ioctl(fd: RawFd, request: c_ulong, argument: *mut libc::c_void) -> Result<(), std::io::Error> {
+    let result = unsafe { libc::ioctl(fd, request, argument) };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::sync::{Arc, Mutex};
+
+use super::GpioBackend;
+use crate::reader::ReaderError;
+
+/// Lines and level of one `set` call.
+pub type Change = (Vec<u32>, bool);
+
+/// In-memory GPIO backend that records every change.
+///
+/// Clones share the same record, so a test can hand one clone to a player and keep another
+/// to inspect what was driven.
+#[derive(Debug, Clone, Default)]
+pub struct MockGpio {
+    changes: Arc<Mutex<Vec<Change>>>,
+}
+
+impl MockGpio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every `set` call so far, oldest first.
+    pub fn changes(&self) -> Vec<Change> {
+        self.changes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+impl GpioBackend for MockGpio {
+    fn set(&mut self, lines: &[u32], active: bool) -> Result<(), ReaderError> {
+        self.changes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((lines.to_vec(), active));
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+pub mod cdev;
+#[cfg(any(test, feature = "test-support"))]
+pub mod mock;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::reader::ReaderError;
+
+/// Pins of the PN532's P3 port that WriteGPIO can drive (P30–P35).
+const PN532_P3_LINES: u32 = 6;
+
+/// What happened to a punch, as far as the person at the terminal needs to know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    PunchedIn,
+    PunchedOut,
+    LeaveRegistered,
+    /// The card was read, but nothing new was recorded: the anti-passback interval had not
+    /// passed, or a toggle tap found the card already punched in.
+    AlreadyRecorded,
+    UnknownCard,
+    Error,
+}
+
+/// One step of a pattern: `outputs` are active for `on_ms`, then idle for `off_ms`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Pulse {
+    /// Output names, as configured in `lines` ("buzzer", "green", "red").
+    pub outputs: Vec<String>,
+    pub on_ms: u64,
+    #[serde(default)]
+    pub off_ms: u64,
+}
+
+impl Pulse {
+    fn new(outputs: &[&str], on_ms: u64, off_ms: u64) -> Self {
+        Self {
+            outputs: outputs.iter().map(|output| output.to_string()).collect(),
+            on_ms,
+            off_ms,
+        }
+    }
+}
+
+/// The pattern played for `outcome` when the configuration does not override it: one short
+/// chirp for success, two for leave, a silent green blink when nothing new was recorded, long
+/// red for an unknown card and three red beeps on errors.
+pub fn default_pattern(outcome: Outcome) -> Vec<Pulse> {
+    match outcome {
+        Outcome::PunchedIn => vec![Pulse::new(&["green", "buzzer"], 150, 0)],
+        Outcome::PunchedOut => vec![Pulse::new(&["green", "buzzer"], 80, 80), Pulse::new(&["green"], 300, 0)],
+        Outcome::LeaveRegistered => vec![
+            Pulse::new(&["green", "buzzer"], 80, 80),
+            Pulse::new(&["green", "buzzer"], 80, 0),
+        ],
+        Outcome::AlreadyRecorded => vec![Pulse::new(&["green"], 300, 0)],
+        Outcome::UnknownCard => vec![Pulse::new(&["red", "buzzer"], 600, 0)],
+        Outcome::Error => vec![
+            Pulse::new(&["red", "buzzer"], 100, 100),
+            Pulse::new(&["red", "buzzer"], 100, 100),
+            Pulse::new(&["red", "buzzer"], 100, 0),
+        ],
+    }
+}
+
+/// Output lines that can be switched on and off together.
+pub trait GpioBackend: Send {
+    /// Drives `lines` to their active level, or back to idle. Lines not listed keep their state.
+    fn set(&mut self, lines: &[u32], active: bool) -> Result<(), ReaderError>;
+}
+
+/// Sends a WriteGPIO P3 byte through whatever owns the PN532.
+pub type Pn532GpioWriter = Box<dyn FnMut(u8) -> Result<(), ReaderError> + Send>;
+
+/// Drives the PN532's own P3x pins, e.g. the buzzer and LEDs of reader boards that wire them up.
+pub struct Pn532Gpio {
+    write: Pn532GpioWriter,
+    active_low: bool,
+    /// Active lines, bit n standing for P3n.
+    active: u8,
+}
+
+impl Pn532Gpio {
+    pub fn new(lines: &[u32], active_low: bool, write: Pn532GpioWriter) -> Result<Self, ReaderError> {
+        if let Some(line) = lines.iter().find(|line| **line >= PN532_P3_LINES) {
+            return Err(ReaderError::Initialization(format!(
+                "PN532 GPIO line {} does not exist, use 0-5 for P30-P35",
+                line
+            )));
+        }
+        Ok(Self {
+            write,
+            active_low,
+            active: 0,
+        })
+    }
+}
+
+impl GpioBackend for Pn532Gpio {
+    fn set(&mut self, lines: &[u32], active: bool) -> Result<(), ReaderError> {
+        let mask = lines.iter().fold(0u8, |mask, line| mask | 1 << line);
+        if active {
+            self.active |= mask;
+        } else {
+            self.active &= !mask;
+        }
+        let levels = if self.active_low { !self.active } else { self.active };
+        (self.write)(levels & 0x3F)
+    }
+}
+
+/// Plays the pattern of an outcome on a GPIO backend.
+pub struct Player {
+    backend: Box<dyn GpioBackend>,
+    lines: HashMap<String, u32>,
+    patterns: HashMap<Outcome, Vec<Pulse>>,
+}
+
+impl Player {
+    /// `lines` maps output names to backend lines; outputs a pattern names but `lines` does
+    /// not are skipped, so the default patterns work on boards with only a buzzer.
+    pub fn new(
+        backend: Box<dyn GpioBackend>,
+        lines: HashMap<String, u32>,
+        patterns: HashMap<Outcome, Vec<Pulse>>,
+    ) -> Self {
+        Self {
+            backend,
+            lines,
+            patterns,
+        }
+    }
+
+    pub fn pattern(&self, outcome: Outcome) -> Vec<Pulse> {
+        self.patterns
+            .get(&outcome)
+            .cloned()
+            .unwrap_or_else(|| default_pattern(outcome))
+    }
+
+    pub fn play(&mut self, outcome: Outcome) -> Result<(), ReaderError> {
+        for pulse in self.pattern(outcome) {
+            let lines: Vec<u32> = pulse
+                .outputs
+                .iter()
+                .filter_map(|output| self.lines.get(output).copied())
+                .collect();
+            if lines.is_empty() {
+                thread::sleep(Duration::from_millis(pulse.on_ms + pulse.off_ms));
+                continue;
+            }
+            self.backend.set(&lines, true)?;
+            thread::sleep(Duration::from_millis(pulse.on_ms));
+            self.backend.set(&lines, false)?;
+            thread::sleep(Duration::from_millis(pulse.off_ms));
+        }
+        Ok(())
+    }
+}
+
+/// Plays outcomes on a background thread, so a punch never waits for the buzzer.
+pub struct Feedback {
+    sender: Sender<Outcome>,
+}
+
+impl Feedback {
+    pub fn spawn(mut player: Player) -> Self {
+        let (sender, receiver) = mpsc::channel::<Outcome>();
+        thread::spawn(move || {
+            while let Ok(outcome) = receiver.recv() {
+                // Someone tapping repeatedly only needs to hear about the latest punch.
+                let outcome = receiver.try_iter().last().unwrap_or(outcome);
+                if let Err(e) = player.play(outcome) {
+                    println!("Punch feedback failed: {}", e);
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn signal(&self, outcome: Outcome) {
+        let _ = self.sender.send(outcome);
+    }
+}
@@ -6,16 +6,16 @@ fn get_all_cards() -> Result<Vec<CardDto>, String> {
     Ok(all_cards.into_iter().map(|c| c.into()).collect())
 }
 
-#[tauri::command(rename_all = "camelCase")]
-fn update_card_info(cardId: i32, cardName: Option<String>, userFullname: Option<String>, userId: Option<String>) -> Result<(), String> {
-    use crate::schema::cards::dsl::*;
+#[tauri::command]
+fn update_card_info(card_id: i32, card_name: Option<String>, user_fullname: Option<String>, user_id: Option<String>) -> Result<(), String> {
+    use crate::schema::cards::dsl;
     let mut conn = establish_connection();
-    diesel::update(cards.filter(id.eq(cardId)))
+    diesel::update(dsl::cards.filter(dsl::id.eq(card_id)))
         .set((
-            card_name.eq(cardName),
-            user_fullname.eq(userFullname),
-            user_id.eq(userId),
-            updated_at.eq(chrono::Utc::now().naive_utc()),
+            dsl::card_name.eq(card_name),
+            dsl::user_fullname.eq(user_fullname),
+            dsl::user_id.eq(user_id),
+            dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
         ))
         .execute(&mut conn)
         .map_err(|e| format!("Update error: {}", e))?;
@@ -38,6 +38,8 @@ pub struct PunchLogDto {
     pub user_fullname: Option<String>,
     pub status: String,
     pub timestamp: String,
+    pub credential_type: Option<String>,
+    pub reader_name: Option<String>,
 }
 
 #[tauri::command]
@@ -58,10 +60,12 @@ fn get_punch_log() -> Result<Vec<PunchLogDto>, String> {
             card_name: card.card_name,
             user_fullname: card.user_fullname,
             status: punch.status,
-            timestamp: chrono::DateTime::<chrono::Utc>::from_utc(punch.timestamp, chrono::Utc)
+            timestamp: chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(punch.timestamp, chrono::Utc)
                 .with_timezone(&chrono_tz::Europe::Ljubljana)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string(),
+            credential_type: punch.credential_type,
+            reader_name: punch.reader_name,
         })
         .collect())
 }
@@ -69,82 +73,191 @@ fn get_punch_log() -> Result<Vec<PunchLogDto>, String> {
 pub struct CardDto {
     pub id: i32,
     pub card_number: String,
+    /// The card number in the configured display format.
+    pub display_number: String,
+    /// Every accepted way of writing the card number, display format first.
+    pub numbers: Vec<FormattedNumber>,
     pub card_name: Option<String>,
     pub user_fullname: Option<String>,
     pub user_id: Option<String>,
     pub is_present: bool,
+    pub has_pin: bool,
 }
 
 impl From<Card> for CardDto {
     fn from(card: Card) -> Self {
+        let numbers = hex_to_uid(&card.card_number)
+            .map(|uid| config::CONFIG.card_numbers.representations(&uid))
+            .unwrap_or_default();
         CardDto {
             id: card.id,
+            display_number: numbers
+                .first()
+                .map(|number| number.number.clone())
+                .unwrap_or_else(|| card.card_number.clone()),
+            numbers,
             card_number: card.card_number,
             card_name: card.card_name,
             user_fullname: card.user_fullname,
             user_id: card.user_id,
             is_present: card.is_present,
+            has_pin: card.pin_hash.is_some(),
         }
     }
 }
+use badges::link_badge;
+use card_number::FormattedNumber;
 use chrono::Utc;
 use diesel::prelude::*;
+use feedback::{Feedback, Outcome};
 use models::{Card, NewCard};
+use config::ReaderRole;
 use once_cell::sync::Lazy;
-use reader::{Reader, ReaderError};
-use reader::pn532::Pn532Reader;
-use std::sync::Mutex;
+use punch_log::{log_pin_punch, log_punch, PunchSource};
+use reader::supervisor::ReaderHealth;
+use reader::{CardFamily, ReaderDiagnostics};
+use readers::ReaderReport;
+use scan::{ScanFinishedEvent, SCANS, SCAN_CANCELLED_ERROR, SCAN_FINISHED_EVENT};
 use std::time::Duration;
+use tauri::{AppHandle, Emitter};
 
-static ACTIVE_READER: Lazy<Mutex<Option<Box<dyn Reader>>>> = Lazy::new(|| Mutex::new(None));
-
-fn read_from_reader(timeout: Duration) -> Result<Vec<u8>, ReaderError> {
-    let mut guard = ACTIVE_READER
-        .lock()
-        .map_err(|_| ReaderError::Device("Reader mutex poisoned".into()))?;
+static FEEDBACK: Lazy<Option<Feedback>> = Lazy::new(|| {
+    let config = config::CONFIG.feedback.as_ref()?;
+    let slot = match config.pn532_reader() {
+        Some(name) => match readers::find(name) {
+            Some(slot) => slot,
+            None => {
+                println!("Punch feedback disabled: no reader named '{}'", name);
+                return None;
+            }
+        },
+        None => readers::primary(),
+    };
+    match config.open(Box::new(move |levels| slot.write_gpio(levels))) {
+        Ok(player) => Some(Feedback::spawn(player)),
+        Err(e) => {
+            println!("Punch feedback disabled: {}", e);
+            None
+        }
+    }
+});
 
-    if guard.is_none() {
-        let mut reader = Pn532Reader::with_default_path()?;
-        reader.init()?;
-        *guard = Some(Box::new(reader));
+fn signal_outcome(outcome: Outcome) {
+    if let Some(feedback) = FEEDBACK.as_ref() {
+        feedback.signal(outcome);
     }
+}
+
+/// Translation key returned when a leave-only reader is tapped without choosing a leave.
+const LEAVE_ONLY_ERROR: &str = "Choose the type of leave before tapping this reader";
+
+/// Translation key returned when a phone or privacy card presents a random UID.
+const RANDOM_UID_ERROR: &str = "This card shows a different number on every tap and cannot be used. Please use your badge.";
+
+/// Translation key returned when the credential mode needs an employee number the card did
+/// not give.
+const EMPLOYEE_ID_ERROR: &str = "Could not read the employee number from the card";
+
+/// Translation key returned when a tag fails the NXP originality check under the
+/// `require` policy.
+const ORIGINALITY_ERROR: &str = "This tag is not a genuine badge and cannot be used";
 
-    let reader = guard
-        .as_mut()
-        .ok_or_else(|| ReaderError::Initialization("Reader not available".into()))?;
-    reader.read_uid(timeout)
+/// Translation key returned when a phone's token belongs to no registered employee.
+const PHONE_NOT_REGISTERED_ERROR: &str = "This phone is not registered to an employee";
+
+/// Translation key returned for a QR code with a signature that does not verify.
+const QR_INVALID_ERROR: &str = "This QR code is not valid";
+
+/// Translation key returned for a QR code past its expiry.
+const QR_EXPIRED_ERROR: &str = "This QR code has expired";
+
+/// Translation key returned when a QR code is requested without an employee number.
+const QR_EMPLOYEE_ERROR: &str = "Enter the employee number the QR code is for";
+
+/// Translation key returned when QR codes are requested without a key configured.
+const QR_DISABLED_ERROR: &str = "QR codes are not enabled on this terminal";
+
+fn hex_to_uid(uid_hex: &str) -> Option<Vec<u8>> {
+    uid_hex
+        .split(':')
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect()
+}
+
+fn uid_to_hex(uid: &[u8]) -> String {
+    uid.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
 }
 
 
+/// Translation key returned when a card number is not written in any accepted format.
+const CARD_NUMBER_ERROR: &str = "This is not a valid card number";
+
+/// Finds the card `number` stands for, written in any accepted format.
+fn find_card_row(conn: &mut SqliteConnection, number: &str) -> QueryResult<Card> {
+    use crate::schema::cards::dsl::*;
+    for uid in config::CONFIG.card_numbers.candidates(number) {
+        match cards.filter(card_number.eq(uid_to_hex(&uid))).first::<Card>(conn) {
+            Err(diesel::result::Error::NotFound) => continue,
+            row => return row,
+        }
+    }
+    Err(diesel::result::Error::NotFound)
+}
+
 #[tauri::command]
 fn find_or_create_user(card_uid: String, _user_fullname: Option<String>) -> Result<CardDto, String> {
     use crate::schema::cards::dsl::*;
+    let uid = config::CONFIG
+        .card_numbers
+        .candidates(&card_uid)
+        .into_iter()
+        .next()
+        .ok_or_else(|| CARD_NUMBER_ERROR.to_string())?;
     let mut conn = establish_connection();
-    match cards.filter(card_number.eq(&card_uid)).first::<Card>(&mut conn) {
+    match find_card_row(&mut conn, &card_uid) {
         Ok(card) => Ok(card.into()),
         Err(diesel::result::Error::NotFound) => {
+            let uid_hex = uid_to_hex(&uid);
             let new_card = NewCard {
-                card_number: &card_uid,
+                card_number: &uid_hex,
                 card_name: None,
+                user_id: None,
             };
             diesel::insert_into(cards)
                 .values(&new_card)
                 .execute(&mut conn)
                 .map_err(|e| format!("Insert error: {}", e))?;
-            cards.filter(card_number.eq(&card_uid)).first::<Card>(&mut conn).map(|c| c.into()).map_err(|e| format!("Fetch error: {}", e))
+            cards.filter(card_number.eq(&uid_hex)).first::<Card>(&mut conn).map(|c| c.into()).map_err(|e| format!("Fetch error: {}", e))
         }
         Err(e) => Err(format!("DB error: {}", e)),
     }
 }
 
+/// Looks a card up by its number written in any accepted format, without enrolling it.
 #[tauri::command]
-fn register_leave(card_uid: String, leave_type: String) -> Result<String, String> {
+fn find_card(number: String) -> Result<Option<CardDto>, String> {
+    let mut conn = establish_connection();
+    match find_card_row(&mut conn, &number) {
+        Ok(card) => Ok(Some(card.into())),
+        Err(diesel::result::Error::NotFound) => Ok(None),
+        Err(e) => Err(format!("DB error: {}", e)),
+    }
+}
+
+/// The configured reader called `name`, so punches only ever record real readers.
+fn known_reader(name: Option<String>) -> Option<String> {
+    name.filter(|name| readers::find(name).is_some())
+}
+
+/// Registers leave for a card after the tap that `reader_name` reported.
+#[tauri::command]
+fn register_leave(card_uid: String, leave_type: String, reader_name: Option<String>) -> Result<String, String> {
     use crate::schema::cards::dsl::*;
     use crate::schema::punches::dsl as punches_dsl;
     let mut conn = establish_connection();
     let now = Utc::now().naive_utc();
-    let card = cards.filter(card_number.eq(&card_uid)).first::<Card>(&mut conn).map_err(|_| "Card not found".to_string())?;
-    let updated = diesel::update(cards.filter(card_number.eq(&card_uid)))
+    let card = find_card_row(&mut conn, &card_uid).map_err(|_| "Card not found".to_string())?;
+    diesel::update(cards.filter(id.eq(card.id)))
         .set((is_present.eq(true), updated_at.eq(now)))
         .execute(&mut conn)
         .map_err(|e| format!("Update error: {}", e))?;
@@ -156,30 +269,41 @@ fn register_leave(card_uid: String, leave_type: String) -> Result<String, String
         .ok();
     let should_log = match last_punch {
         Some(ref punch) => {
-            let diff = now.signed_duration_since(punch.timestamp).num_seconds();
-            punch.status != leave_type || diff > 1
+            punch.status != leave_type || config::CONFIG.anti_passback.allows(punch.timestamp, now)
         },
         None => true,
     };
     if should_log {
+        let reader_name = known_reader(reader_name);
         let new_punch = models::NewPunch {
             card_id: card.id,
             status: &leave_type,
+            credential_type: None,
+            reader_name: reader_name.as_deref(),
         };
         diesel::insert_into(punches_dsl::punches)
             .values(&new_punch)
             .execute(&mut conn)
             .map_err(|e| format!("Punch log error: {}", e))?;
+        signal_outcome(Outcome::LeaveRegistered);
     }
     Ok(format!("Leave '{}' registered for card {}", leave_type, card_uid))
 }
+pub mod badges;
+pub mod card_number;
+pub mod config;
+pub mod feedback;
 pub mod models;
+pub mod pin;
+pub mod punch_log;
 pub mod reader;
+pub mod readers;
+pub mod scan;
 pub mod schema;
+pub mod worker;
 
 use app_dirs2::*;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use std::env;
 
 const APP_INFO: AppInfo = AppInfo {
     name: "Terminal",
@@ -188,16 +312,17 @@ const APP_INFO: AppInfo = AppInfo {
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
-pub fn establish_connection() -> SqliteConnection {
-    let app_dir =
-        app_root(AppDataType::UserConfig, &APP_INFO).expect("Failed to get app root directory");
+pub fn app_dir() -> std::path::PathBuf {
+    app_root(AppDataType::UserConfig, &APP_INFO).expect("Failed to get app root directory")
+}
 
-    let database_path = app_dir.join("terminal.db");
+pub fn establish_connection() -> SqliteConnection {
+    let database_path = app_dir().join("terminal.db");
     let database_url = database_path
         .to_str()
         .expect("Failed to convert path to string");
 
-    SqliteConnection::establish(&database_url)
+    SqliteConnection::establish(database_url)
         .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
 }
 
@@ -213,91 +338,273 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+fn scan_timeout(timeout: Option<i32>) -> Duration {
+    let seconds = timeout
+        .and_then(|t| if t > 0 { Some(t as u64) } else { None })
+        .unwrap_or(15);
+    Duration::from_secs(seconds)
+}
+
+/// Translation key returned for a tap that was already punched or is too old to punch.
+const TAP_EXPIRED_ERROR: &str = "This tap is no longer valid. Please tap your card again.";
+
+/// Punches the tap a `card-scanned` event reported as `tap_id`, or without one waits for
+/// the next card. Only cards a reader produced can be punched.
 #[tauri::command]
-fn read_card_uid(_timeout: Option<i32>, is_leave: Option<bool>, uid: Option<Vec<u8>>, leave_type: Option<String>) -> Result<Vec<u8>, String> {
-    let resolved_uid = match uid {
-        Some(mock) => mock,
+fn read_card_uid(_timeout: Option<i32>, is_leave: Option<bool>, tap_id: Option<u64>, leave_type: Option<String>) -> Result<Vec<u8>, String> {
+    let scanned = match tap_id {
+        Some(tap_id) => worker::TAPS.take(tap_id).ok_or_else(|| TAP_EXPIRED_ERROR.to_string())?,
         None => {
-            let timeout = _timeout
-                .and_then(|t| if t > 0 { Some(t as u64) } else { None })
-                .unwrap_or(15);
-            read_from_reader(Duration::from_secs(timeout)).map_err(|e| e.to_string())?
+            let session = SCANS.start()?;
+            match session.wait(scan_timeout(_timeout)).map_err(|e| e.to_string())? {
+                Some(scanned) => scanned,
+                None => return Err(SCAN_CANCELLED_ERROR.to_string()),
+            }
         }
     };
-    let uid_hex = resolved_uid.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":");
-    println!("read_card_uid called with UID: {}", uid_hex);
-    use crate::schema::cards::dsl::*;
-    use crate::schema::punches::dsl as punches_dsl;
-    let mut conn = establish_connection();
+    record_punch(scanned, is_leave, leave_type)
+}
 
-    if let Ok(card) = cards.filter(card_number.eq(&uid_hex)).first::<Card>(&mut conn) {
-        use diesel::prelude::*;
-        use chrono::NaiveDateTime;
-        let last_punch = punches_dsl::punches
-            .filter(punches_dsl::card_id.eq(card.id))
-            .order(punches_dsl::timestamp.desc())
-            .first::<models::Punch>(&mut conn)
-            .ok();
-        let now = chrono::Utc::now().naive_utc();
-        let should_log = match last_punch {
-            Some(ref punch) => {
-                let diff = now.signed_duration_since(punch.timestamp).num_seconds();
-                diff > 1
-            },
-            None => true,
-        };
-        if should_log {
-            let is_leave_scan = is_leave.unwrap_or(false);
-            if is_leave_scan {
-                let status_str = leave_type.as_deref().unwrap_or("leave_type.unknown");
-                let new_punch = models::NewPunch {
-                    card_id: card.id,
-                    status: status_str,
-                };
-                let _ = diesel::insert_into(punches_dsl::punches)
-                    .values(&new_punch)
-                    .execute(&mut conn);
-            } else {
-                let should_punch_in = match &last_punch {
-                    None => true,
-                    Some(p) => p.status != "In",
-                };
-                if should_punch_in {
-                    let new_punch = models::NewPunch {
-                        card_id: card.id,
-                        status: "In",
+/// Starts waiting for a card without blocking and returns the scan's id. The punch it
+/// leads to, an error, or the cancellation arrives as a `scan-finished` event.
+#[tauri::command]
+fn start_scan(app: AppHandle, timeout: Option<i32>, is_leave: Option<bool>, leave_type: Option<String>) -> Result<u64, String> {
+    let session = SCANS.start()?;
+    let scan_id = session.id;
+    let timeout = scan_timeout(timeout);
+    std::thread::Builder::new()
+        .name(format!("scan-{}", scan_id))
+        .spawn(move || {
+            let waited = session.wait(timeout);
+            // Free the reader before the frontend hears about it and starts the next scan.
+            drop(session);
+            let event = match waited {
+                Ok(Some(scanned)) => {
+                    let reader_name = Some(scanned.reader_name.clone());
+                    let (uid, error) = match record_punch(scanned, is_leave, leave_type) {
+                        Ok(uid) => (Some(uid), None),
+                        Err(e) => (None, Some(e)),
                     };
-                    let _ = diesel::insert_into(punches_dsl::punches)
-                        .values(&new_punch)
-                        .execute(&mut conn);
+                    ScanFinishedEvent { scan_id, uid, reader_name, error, cancelled: false }
                 }
+                Ok(None) => ScanFinishedEvent { scan_id, uid: None, reader_name: None, error: None, cancelled: true },
+                Err(e) => ScanFinishedEvent { scan_id, uid: None, reader_name: None, error: Some(e.to_string()), cancelled: false },
+            };
+            if let Err(e) = app.emit(SCAN_FINISHED_EVENT, event) {
+                println!("Failed to emit {}: {}", SCAN_FINISHED_EVENT, e);
             }
+        })
+        .map_err(|e| format!("Failed to start scan: {}", e))?;
+    Ok(scan_id)
+}
+
+/// Cancels a scan started with `start_scan`. Returns `false` if it had already finished.
+#[tauri::command]
+fn cancel_scan(scan_id: u64) -> bool {
+    SCANS.cancel(scan_id)
+}
+
+/// Sets or, with `None`, removes the PIN an employee can punch with instead of the badge.
+#[tauri::command]
+fn set_card_pin(card_id: i32, pin: Option<String>) -> Result<(), String> {
+    use crate::schema::cards::dsl::*;
+    let hashed = pin.as_deref().map(pin::hash).transpose()?;
+    let mut conn = establish_connection();
+    diesel::update(cards.filter(id.eq(card_id)))
+        .set((
+            pin_hash.eq(hashed),
+            pin_failures.eq(0),
+            pin_locked_until.eq(None::<chrono::NaiveDateTime>),
+            updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(&mut conn)
+        .map_err(|e| format!("Update error: {}", e))?;
+    Ok(())
+}
+
+/// Punches for an employee who forgot the badge, identified by employee number and PIN.
+/// Returns the card number like `read_card_uid`.
+#[tauri::command]
+fn punch_with_pin(employee_id: String, pin: String, is_leave: Option<bool>, leave_type: Option<String>) -> Result<Vec<u8>, String> {
+    let result = punch_pin(&employee_id, &pin, is_leave.unwrap_or(false), leave_type.as_deref());
+    signal_outcome(match &result {
+        Ok((_, outcome)) => *outcome,
+        Err(_) => Outcome::Error,
+    });
+    result.map(|(card_uid, _)| card_uid)
+}
+
+fn punch_pin(employee_id: &str, entered_pin: &str, is_leave_scan: bool, leave_type: Option<&str>) -> Result<(Vec<u8>, Outcome), String> {
+    let mut conn = establish_connection();
+    let (card, outcome) = log_pin_punch(
+        &mut conn,
+        employee_id,
+        entered_pin,
+        is_leave_scan,
+        leave_type,
+        &config::CONFIG.pin,
+        &config::CONFIG.anti_passback,
+    )?;
+    let card_uid = hex_to_uid(&card.card_number).unwrap_or_default();
+    Ok((card_uid, outcome))
+}
+
+/// Longest a printed QR code may stay valid, in days.
+const MAX_QR_VALID_DAYS: i64 = 366;
+
+/// The payload to print as a QR code for a temporary worker, valid for `valid_days` days
+/// (7 by default, at most [`MAX_QR_VALID_DAYS`]).
+#[tauri::command]
+fn create_qr_payload(employee_id: String, valid_days: Option<i64>) -> Result<String, String> {
+    let employee_id = employee_id.trim();
+    if employee_id.is_empty() {
+        return Err(QR_EMPLOYEE_ERROR.into());
+    }
+    let credential = config::CONFIG
+        .qr
+        .as_ref()
+        .ok_or_else(|| QR_DISABLED_ERROR.to_string())?
+        .credential()
+        .map_err(|e| e.to_string())?;
+    let valid_days = valid_days.filter(|days| *days > 0).unwrap_or(7).min(MAX_QR_VALID_DAYS);
+    let expires = Utc::now() + chrono::Duration::days(valid_days);
+    Ok(credential.sign(employee_id, expires.timestamp()))
+}
+
+/// Punches a scanned card and signals the outcome on the terminal.
+fn record_punch(scanned: worker::ScannedCard, is_leave: Option<bool>, leave_type: Option<String>) -> Result<Vec<u8>, String> {
+    let result = punch_card(scanned, is_leave, leave_type);
+    signal_outcome(match &result {
+        Ok((_, outcome)) => *outcome,
+        Err(_) => Outcome::Error,
+    });
+    result.map(|(card_uid, _)| card_uid)
+}
+
+/// Records the punch of a detected card and returns the number the frontend knows it by.
+fn punch_card(scanned: worker::ScannedCard, is_leave: Option<bool>, leave_type: Option<String>) -> Result<(Vec<u8>, Outcome), String> {
+    let worker::ScannedCard { card: detected, reader_name } = scanned;
+    let role = readers::find(&reader_name).map(|slot| slot.role).unwrap_or_default();
+    let is_leave_scan = is_leave.unwrap_or(false);
+    if role == ReaderRole::LeaveOnly && !is_leave_scan {
+        return Err(LEAVE_ONLY_ERROR.into());
+    }
+    let credential_type = detected.family.as_str();
+    let uid_hex = uid_to_hex(&detected.uid);
+    println!("read_card_uid called with UID: {}", uid_hex);
+    // Phones present a new random UID on every tap and are identified by their signed token.
+    let phone_token = match detected.family {
+        CardFamily::IsoDep => detected.credential.clone(),
+        _ => None,
+    };
+    if detected.uid_kind.is_ephemeral() && phone_token.is_none() {
+        println!("Refusing random UID {}", uid_hex);
+        return Err(RANDOM_UID_ERROR.into());
+    }
+    // A QR code comes without an employee number only when it did not verify.
+    let is_qr_code = detected.family == CardFamily::QrCode;
+    if is_qr_code && detected.credential.is_none() {
+        println!("Refusing QR code {}", uid_hex);
+        return Err(match detected.signature_valid {
+            Some(true) => QR_EXPIRED_ERROR,
+            _ => QR_INVALID_ERROR,
         }
+        .into());
     }
-    Ok(resolved_uid)
+    use crate::schema::cards::dsl::*;
+    let mut conn = establish_connection();
+
+    if !config::CONFIG.originality.admits(&detected) {
+        println!("Refusing card {} that did not prove it is genuine", uid_hex);
+        return Err(ORIGINALITY_ERROR.into());
+    }
+    let employee_id = detected.credential.clone();
+    if employee_id.is_none() && config::CONFIG.credential.requires_employee_id() {
+        return Err(EMPLOYEE_ID_ERROR.into());
+    }
+    let card_row = match (phone_token, employee_id) {
+        (Some(ref token), _) => match cards.filter(user_id.eq(token)).first::<Card>(&mut conn) {
+            Err(diesel::result::Error::NotFound) => return Err(PHONE_NOT_REGISTERED_ERROR.into()),
+            row => row,
+        },
+        // A printed code punches the employee's row without re-pointing it from their badge.
+        (None, Some(ref employee_id)) if is_qr_code => match cards.filter(user_id.eq(employee_id)).first::<Card>(&mut conn) {
+            Err(diesel::result::Error::NotFound) => {
+                link_badge(&mut conn, employee_id, &uid_hex)?;
+                cards.filter(user_id.eq(employee_id)).first::<Card>(&mut conn)
+            }
+            row => row,
+        },
+        (None, Some(ref employee_id)) => {
+            link_badge(&mut conn, employee_id, &uid_hex)?;
+            cards.filter(user_id.eq(employee_id)).first::<Card>(&mut conn)
+        }
+        (None, None) => cards.filter(card_number.eq(&uid_hex)).first::<Card>(&mut conn),
+    };
+    // The frontend addresses the card by number, which for a phone is its owner's badge.
+    let card_uid = match &card_row {
+        Ok(card) => hex_to_uid(&card.card_number).unwrap_or_else(|| detected.uid.clone()),
+        Err(_) => detected.uid.clone(),
+    };
+    let outcome = match card_row {
+        Ok(card) => log_punch(
+            &mut conn,
+            &card,
+            role,
+            is_leave_scan,
+            leave_type.as_deref(),
+            PunchSource {
+                credential_type: Some(credential_type),
+                reader_name: Some(&reader_name),
+            },
+            &config::CONFIG.anti_passback,
+        ),
+        Err(_) => Outcome::UnknownCard,
+    };
+    Ok((card_uid, outcome))
 }
 
+/// Supervisor health of every reader; the `reader-connected` and `reader-disconnected`
+/// events carry the same list.
 #[tauri::command]
-fn punch_out(card_uid: String) -> Result<String, String> {
+fn get_reader_status() -> Vec<ReaderReport<ReaderHealth>> {
+    readers::health()
+}
+
+#[tauri::command]
+fn get_reader_diagnostics() -> Result<Vec<ReaderReport<ReaderDiagnostics>>, String> {
+    readers::READERS
+        .iter()
+        .map(|slot| slot.diagnostics().map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Punches a card out after the tap that `reader_name` reported.
+#[tauri::command]
+fn punch_out(card_uid: String, reader_name: Option<String>) -> Result<String, String> {
     use crate::schema::cards::dsl::*;
     use crate::schema::punches::dsl as punches_dsl;
     let mut conn = establish_connection();
     let now = Utc::now().naive_utc();
-    let card = cards.filter(card_number.eq(&card_uid)).first::<Card>(&mut conn).map_err(|_| "Card not found".to_string())?;
+    let card = find_card_row(&mut conn, &card_uid).map_err(|_| "Card not found".to_string())?;
     
-    diesel::update(cards.filter(card_number.eq(&card_uid)))
+    diesel::update(cards.filter(id.eq(card.id)))
         .set((is_present.eq(false), updated_at.eq(now)))
         .execute(&mut conn)
         .map_err(|e| format!("Update error: {}", e))?;
     
+    let reader_name = known_reader(reader_name);
     let new_punch = models::NewPunch {
         card_id: card.id,
         status: "Out",
+        credential_type: None,
+        reader_name: reader_name.as_deref(),
     };
     diesel::insert_into(punches_dsl::punches)
         .values(&new_punch)
         .execute(&mut conn)
         .map_err(|e| format!("Punch log error: {}", e))?;
+    signal_outcome(Outcome::PunchedOut);
     Ok("Punch out registered".to_string())
 }
 
@@ -305,7 +612,12 @@ fn punch_out(card_uid: String) -> Result<String, String> {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-    .invoke_handler(tauri::generate_handler![greet, read_card_uid, find_or_create_user, register_leave, punch_out, get_punch_overview, get_punch_log, clear_punch_logs, get_all_cards, update_card_info, delete_card])
+        .setup(|app| {
+            config::read_config(&config::config_path())?;
+            worker::spawn(app.handle().clone());
+            Ok(())
+        })
+    .invoke_handler(tauri::generate_handler![greet, read_card_uid, start_scan, cancel_scan, find_or_create_user, find_card, set_card_pin, punch_with_pin, create_qr_payload, register_leave, punch_out, get_punch_overview, get_punch_log, clear_punch_logs, get_all_cards, update_card_info, delete_card, get_reader_status, get_reader_diagnostics])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
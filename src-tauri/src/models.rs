@@ -5,6 +5,8 @@ pub struct Punch {
     pub card_id: i32,
     pub status: String,
     pub timestamp: chrono::NaiveDateTime,
+    pub credential_type: Option<String>,
+    pub reader_name: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -12,6 +14,8 @@ pub struct Punch {
 pub struct NewPunch<'a> {
     pub card_id: i32,
     pub status: &'a str,
+    pub credential_type: Option<&'a str>,
+    pub reader_name: Option<&'a str>,
 }
 use diesel::prelude::*;
 
@@ -27,6 +31,10 @@ pub struct Card {
     pub is_present: bool,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    #[serde(skip_serializing)]
+    pub pin_hash: Option<String>,
+    pub pin_failures: i32,
+    pub pin_locked_until: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Insertable)]
@@ -34,4 +42,5 @@ pub struct Card {
 pub struct NewCard<'a> {
     pub card_number: &'a str,
     pub card_name: Option<&'a str>,
+    pub user_id: Option<&'a str>,
 }
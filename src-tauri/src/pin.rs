@@ -0,0 +1,93 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::Read;
+
+use crate::reader::card::parse_hex;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Credential type recorded on punches made with a PIN.
+pub const PIN_CREDENTIAL: &str = "pin";
+
+const SCHEME: &str = "pbkdf2-sha256";
+const ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const MIN_DIGITS: usize = 4;
+const MAX_DIGITS: usize = 8;
+
+/// Translation key returned for a wrong PIN or an employee number without one. Both read
+/// the same so the terminal does not reveal which employee numbers exist.
+pub const PIN_LOGIN_ERROR: &str = "Wrong employee number or PIN";
+
+/// Translation key returned while an employee is locked out after too many wrong PINs.
+pub const PIN_LOCKED_ERROR: &str = "Too many wrong PINs. Try again later or use your badge.";
+
+/// Translation key returned when a PIN is not 4 to 8 digits.
+pub const PIN_FORMAT_ERROR: &str = "The PIN must be 4 to 8 digits";
+
+pub fn validate(pin: &str) -> Result<(), String> {
+    if (MIN_DIGITS..=MAX_DIGITS).contains(&pin.len()) && pin.bytes().all(|b| b.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(PIN_FORMAT_ERROR.to_string())
+    }
+}
+
+/// Hashes `pin` with a fresh salt, as stored in `cards.pin_hash`:
+/// `pbkdf2-sha256$<iterations>$<salt hex>$<hash hex>`.
+pub fn hash(pin: &str) -> Result<String, String> {
+    validate(pin)?;
+    let mut salt = [0u8; SALT_LEN];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut source| source.read_exact(&mut salt))
+        .map_err(|e| format!("No randomness for the PIN salt: {}", e))?;
+    Ok(hash_with_salt(pin, &salt, ITERATIONS))
+}
+
+pub fn hash_with_salt(pin: &str, salt: &[u8], iterations: u32) -> String {
+    format!(
+        "{}${}${}${}",
+        SCHEME,
+        iterations,
+        hex(salt),
+        hex(&pbkdf2(pin.as_bytes(), salt, iterations))
+    )
+}
+
+/// Checks `pin` against a stored hash. A hash in an unknown format never matches.
+pub fn verify(stored: &str, pin: &str) -> bool {
+    let parts: Vec<&str> = stored.split('$').collect();
+    let [scheme, iterations, salt, expected] = parts[..] else {
+        return false;
+    };
+    let (Ok(iterations), Some(salt), Some(expected)) = (iterations.parse::<u32>(), parse_hex(salt), parse_hex(expected)) else {
+        return false;
+    };
+    if scheme != SCHEME || iterations == 0 {
+        return false;
+    }
+    let actual = pbkdf2(pin.as_bytes(), &salt, iterations);
+    // Compare in constant time so the check does not leak how much of the hash matched.
+    expected.len() == actual.len() && expected.iter().zip(actual.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// PBKDF2-HMAC-SHA256 with a single 32-byte output block.
+fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let prf = <HmacSha256 as Mac>::new_from_slice(password).expect("HMAC accepts keys of any length");
+    let mut mac = prf.clone();
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut block: [u8; 32] = mac.finalize().into_bytes().into();
+    let mut result = block;
+    for _ in 1..iterations {
+        let mut mac = prf.clone();
+        mac.update(&block);
+        block = mac.finalize().into_bytes().into();
+        result.iter_mut().zip(block.iter()).for_each(|(r, b)| *r ^= b);
+    }
+    result
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
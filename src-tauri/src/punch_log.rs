@@ -0,0 +1,153 @@
+use diesel::prelude::*;
+
+use crate::config::{AntiPassbackPolicy, PinPolicy, ReaderRole};
+use crate::feedback::Outcome;
+use crate::models::{Card, NewPunch, Punch};
+use crate::pin::{self, PIN_CREDENTIAL, PIN_LOCKED_ERROR, PIN_LOGIN_ERROR};
+
+/// Where a punch came from, stored with it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PunchSource<'a> {
+    /// Card family or other credential, e.g. "mifare_classic_1k" or "pin".
+    pub credential_type: Option<&'a str>,
+    /// Name of the reader that produced the punch.
+    pub reader_name: Option<&'a str>,
+}
+
+/// Records a punch of a known card the way a tap on a reader with `role` would, leaving
+/// it out when `anti_passback` has not passed since the card's last punch.
+///
+/// Returns [`Outcome::AlreadyRecorded`] when nothing was logged, which is also the case for
+/// a toggle tap of a card that is already punched in.
+pub fn log_punch(
+    conn: &mut SqliteConnection,
+    card: &Card,
+    role: ReaderRole,
+    is_leave_scan: bool,
+    leave_type: Option<&str>,
+    source: PunchSource,
+    anti_passback: &AntiPassbackPolicy,
+) -> Outcome {
+    use crate::schema::cards::dsl::*;
+    use crate::schema::punches::dsl as punches_dsl;
+    let last_punch = punches_dsl::punches
+        .filter(punches_dsl::card_id.eq(card.id))
+        .order(punches_dsl::timestamp.desc())
+        .first::<Punch>(conn)
+        .ok();
+    let now = chrono::Utc::now().naive_utc();
+    let should_log = match last_punch {
+        Some(ref punch) => anti_passback.allows(punch.timestamp, now),
+        None => true,
+    };
+    if !should_log {
+        return Outcome::AlreadyRecorded;
+    }
+    let (status_str, outcome) = if is_leave_scan {
+        (Some(leave_type.unwrap_or("leave_type.unknown")), Outcome::LeaveRegistered)
+    } else {
+        match role {
+            ReaderRole::Entry => (Some("In"), Outcome::PunchedIn),
+            ReaderRole::Exit => (Some("Out"), Outcome::PunchedOut),
+            _ => match &last_punch {
+                Some(p) if p.status == "In" => (None, Outcome::AlreadyRecorded),
+                _ => (Some("In"), Outcome::PunchedIn),
+            },
+        }
+    };
+    if let Some(status_str) = status_str {
+        let new_punch = NewPunch {
+            card_id: card.id,
+            status: status_str,
+            credential_type: source.credential_type,
+            reader_name: source.reader_name,
+        };
+        if let Err(e) = diesel::insert_into(punches_dsl::punches).values(&new_punch).execute(conn) {
+            println!("Punch log error: {}", e);
+            return Outcome::Error;
+        }
+    }
+    if !is_leave_scan && matches!(role, ReaderRole::Entry | ReaderRole::Exit) {
+        let _ = diesel::update(cards.filter(id.eq(card.id)))
+            .set((is_present.eq(role == ReaderRole::Entry), updated_at.eq(now)))
+            .execute(conn);
+    }
+    outcome
+}
+
+/// Punches `employee_id` in, or out when already in, after checking the PIN they entered
+/// instead of tapping a badge. Both directions are recorded as PIN punches.
+///
+/// The PIN check and the failure count run in one write transaction, so wrong guesses made
+/// at the same time cannot each read the old count and slip past the lockout.
+pub fn log_pin_punch(
+    conn: &mut SqliteConnection,
+    employee_id: &str,
+    entered_pin: &str,
+    is_leave_scan: bool,
+    leave_type: Option<&str>,
+    policy: &PinPolicy,
+    anti_passback: &AntiPassbackPolicy,
+) -> Result<(Card, Outcome), String> {
+    conn.immediate_transaction(|conn| {
+        pin_punch(conn, employee_id, entered_pin, is_leave_scan, leave_type, policy, anti_passback)
+    })
+    .map_err(|e| format!("DB error: {}", e))?
+}
+
+/// The punch, or the refusal to show; only a database error rolls the transaction back, as
+/// a wrong PIN has to keep its count.
+fn pin_punch(
+    conn: &mut SqliteConnection,
+    employee_id: &str,
+    entered_pin: &str,
+    is_leave_scan: bool,
+    leave_type: Option<&str>,
+    policy: &PinPolicy,
+    anti_passback: &AntiPassbackPolicy,
+) -> QueryResult<Result<(Card, Outcome), String>> {
+    use crate::schema::cards::dsl::*;
+    use crate::schema::punches::dsl as punches_dsl;
+    let Some(card) = cards.filter(user_id.eq(employee_id)).first::<Card>(conn).optional()? else {
+        return Ok(Err(PIN_LOGIN_ERROR.into()));
+    };
+    let now = chrono::Utc::now().naive_utc();
+    if policy.is_locked(card.pin_locked_until, now) {
+        return Ok(Err(PIN_LOCKED_ERROR.into()));
+    }
+    let Some(stored) = card.pin_hash.as_deref() else {
+        return Ok(Err(PIN_LOGIN_ERROR.into()));
+    };
+    if !pin::verify(stored, entered_pin) {
+        let (failures, locked_until) = policy.after_failure(card.pin_failures, now);
+        diesel::update(cards.filter(id.eq(card.id)))
+            .set((pin_failures.eq(failures), pin_locked_until.eq(locked_until)))
+            .execute(conn)?;
+        if locked_until.is_some() {
+            println!("PIN punches of employee {} locked after repeated failures", employee_id);
+            return Ok(Err(PIN_LOCKED_ERROR.into()));
+        }
+        return Ok(Err(PIN_LOGIN_ERROR.into()));
+    }
+    if card.pin_failures != 0 || card.pin_locked_until.is_some() {
+        diesel::update(cards.filter(id.eq(card.id)))
+            .set((pin_failures.eq(0), pin_locked_until.eq(None::<chrono::NaiveDateTime>)))
+            .execute(conn)?;
+    }
+    let last_status = punches_dsl::punches
+        .filter(punches_dsl::card_id.eq(card.id))
+        .order((punches_dsl::timestamp.desc(), punches_dsl::id.desc()))
+        .select(punches_dsl::status)
+        .first::<String>(conn)
+        .optional()?;
+    let role = match last_status.as_deref() {
+        Some("In") => ReaderRole::Exit,
+        _ => ReaderRole::Entry,
+    };
+    let source = PunchSource {
+        credential_type: Some(PIN_CREDENTIAL),
+        reader_name: None,
+    };
+    let outcome = log_punch(conn, &card, role, is_leave_scan, leave_type, source, anti_passback);
+    Ok(Ok((card, outcome)))
+}
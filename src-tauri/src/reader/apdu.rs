@@ -0,0 +1,40 @@
+use std::io::Read;
+
+use super::ReaderError;
+
+/// ISO 7816-4 status word for success.
+pub const SW_OK: [u8; 2] = [0x90, 0x00];
+
+/// Exchanges ISO 7816-4 APDUs with an ISO 14443-4 target.
+pub trait ApduChannel {
+    /// Sends `apdu` and returns the answer including its SW1 SW2 status word.
+    fn transceive(&mut self, apdu: &[u8]) -> Result<Vec<u8>, ReaderError>;
+}
+
+/// Builds a case 4 APDU: CLA INS P1 P2 Lc data Le=00.
+pub fn command_apdu(cla: u8, ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![cla, ins, p1, p2, data.len() as u8];
+    apdu.extend_from_slice(data);
+    apdu.push(0x00);
+    apdu
+}
+
+/// Splits an answer into its data and status word.
+pub fn split_status(response: &[u8]) -> Result<(&[u8], [u8; 2]), ReaderError> {
+    match response {
+        [data @ .., sw1, sw2] => Ok((data, [*sw1, *sw2])),
+        _ => Err(ReaderError::Protocol(format!(
+            "APDU answer without status word: {:02x?}",
+            response
+        ))),
+    }
+}
+
+/// Fresh random bytes for a challenge sent to the card.
+pub fn random_challenge<const N: usize>() -> Result<[u8; N], ReaderError> {
+    let mut bytes = [0u8; N];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut source| source.read_exact(&mut bytes))
+        .map_err(|e| ReaderError::Device(format!("No randomness for the card challenge: {}", e)))?;
+    Ok(bytes)
+}
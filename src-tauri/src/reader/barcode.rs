@@ -0,0 +1,284 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::card::parse_hex;
+use super::hid::{HidWedgeReader, WedgeFormat};
+use super::serial::SerialPort;
+use super::{CardFamily, DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// First field of every payload, naming the format version.
+pub const QR_PREFIX: &str = "UNQR1";
+pub const DEFAULT_SCANNER_BAUD_RATE: u32 = 9600;
+const READ_SLICE: Duration = Duration::from_millis(100);
+/// Length of the synthetic UID a code is filed under, that of a double-size NFC UID.
+const QR_UID_LEN: usize = 7;
+
+/// Key for the printed QR codes temporary workers punch with.
+///
+/// A code reads `UNQR1.<employee id>.<expiry>.<signature>`, the expiry in Unix seconds and
+/// the signature the hex HMAC-SHA256 of everything before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCredential {
+    key: Vec<u8>,
+}
+
+/// A code whose signature verified and that has not expired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrPass {
+    pub employee_id: String,
+    pub expires: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrError {
+    Malformed,
+    BadSignature,
+    Expired,
+}
+
+impl QrCredential {
+    pub fn new(key_hex: &str) -> Result<Self, ReaderError> {
+        let key = parse_hex(key_hex)
+            .filter(|key| key.len() >= 16)
+            .ok_or_else(|| ReaderError::Initialization("QR code key must be at least 16 bytes".into()))?;
+        Ok(Self { key })
+    }
+
+    fn mac(&self, signed: &str) -> HmacSha256 {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(signed.as_bytes());
+        mac
+    }
+
+    /// The payload to print for `employee_id`, valid until `expires`.
+    pub fn sign(&self, employee_id: &str, expires: i64) -> String {
+        let signed = format!("{}.{}.{}", QR_PREFIX, employee_id, expires);
+        let signature = self.mac(&signed).finalize().into_bytes();
+        let signature: String = signature.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}.{}", signed, signature)
+    }
+
+    /// Checks the signature and expiry of a scanned payload at Unix time `now`.
+    pub fn verify(&self, payload: &str, now: i64) -> Result<QrPass, QrError> {
+        let (signed, signature) = payload.trim().rsplit_once('.').ok_or(QrError::Malformed)?;
+        let signature = parse_hex(signature).ok_or(QrError::Malformed)?;
+        let (employee_id, expires) = signed
+            .strip_prefix(QR_PREFIX)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .and_then(|rest| rest.rsplit_once('.'))
+            .ok_or(QrError::Malformed)?;
+        let expires: i64 = expires.parse().map_err(|_| QrError::Malformed)?;
+        if employee_id.is_empty() {
+            return Err(QrError::Malformed);
+        }
+        self.mac(signed).verify_slice(&signature).map_err(|_| QrError::BadSignature)?;
+        if now >= expires {
+            return Err(QrError::Expired);
+        }
+        Ok(QrPass {
+            employee_id: employee_id.to_string(),
+            expires,
+        })
+    }
+}
+
+/// The UID a code is filed under. It depends only on the employee, so every code printed
+/// for someone punches the same card row.
+pub fn qr_uid(employee_id: &str) -> Vec<u8> {
+    let digest = Sha256::digest(format!("{}.{}", QR_PREFIX, employee_id).as_bytes());
+    digest[..QR_UID_LEN].to_vec()
+}
+
+/// Where the scanner's lines come from.
+pub trait LineSource: Send {
+    fn open(&mut self) -> Result<(), ReaderError>;
+
+    /// Waits up to `timeout` for the next non-empty line.
+    fn read_line(&mut self, timeout: Duration) -> Result<String, ReaderError>;
+
+    fn path(&self) -> String;
+
+    fn transport(&self) -> &'static str;
+}
+
+/// Replays scripted lines; once they run out, reads time out.
+impl LineSource for VecDeque<String> {
+    fn open(&mut self) -> Result<(), ReaderError> {
+        Ok(())
+    }
+
+    fn read_line(&mut self, _timeout: Duration) -> Result<String, ReaderError> {
+        self.pop_front().ok_or(ReaderError::Timeout)
+    }
+
+    fn path(&self) -> String {
+        String::from("scripted")
+    }
+
+    fn transport(&self) -> &'static str {
+        "mock"
+    }
+}
+
+/// Scanner in keyboard mode, decoded like a keyboard-wedge card reader.
+impl LineSource for HidWedgeReader {
+    fn open(&mut self) -> Result<(), ReaderError> {
+        self.init()
+    }
+
+    fn read_line(&mut self, timeout: Duration) -> Result<String, ReaderError> {
+        HidWedgeReader::read_line(self, timeout)
+    }
+
+    fn path(&self) -> String {
+        HidWedgeReader::path(self).to_string()
+    }
+
+    fn transport(&self) -> &'static str {
+        "evdev"
+    }
+}
+
+/// Scanner in serial mode, ending every code with CR, LF or both.
+pub struct SerialLines {
+    path: String,
+    baud_rate: u32,
+    port: Option<SerialPort>,
+    buffer: Vec<u8>,
+}
+
+impl SerialLines {
+    pub fn new(path: impl Into<String>, baud_rate: u32) -> Self {
+        Self {
+            path: path.into(),
+            baud_rate,
+            port: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn take_line(&mut self) -> Option<String> {
+        while let Some(end) = self.buffer.iter().position(|b| matches!(b, b'\r' | b'\n')) {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                return Some(line);
+            }
+        }
+        None
+    }
+}
+
+impl LineSource for SerialLines {
+    fn open(&mut self) -> Result<(), ReaderError> {
+        if self.port.is_none() {
+            self.port = Some(SerialPort::open(&self.path, self.baud_rate, READ_SLICE)?);
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    fn read_line(&mut self, timeout: Duration) -> Result<String, ReaderError> {
+        self.open()?;
+        let start = Instant::now();
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(line);
+            }
+            if start.elapsed() >= timeout {
+                return Err(ReaderError::Timeout);
+            }
+            let port = self
+                .port
+                .as_mut()
+                .ok_or_else(|| ReaderError::Initialization("Serial port not open".into()))?;
+            let mut chunk = [0u8; 256];
+            let count = port.read(&mut chunk)?;
+            self.buffer.extend_from_slice(&chunk[..count]);
+        }
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn transport(&self) -> &'static str {
+        "uart"
+    }
+}
+
+/// Barcode scanner reading the signed QR codes of temporary workers.
+///
+/// A valid code is reported as a card carrying the employee ID, so it punches through
+/// the same pipeline as a badge. A code with a bad signature or past its expiry is still
+/// reported, without an employee ID, so the terminal can tell the worker; anything that is
+/// not one of our codes is ignored.
+pub struct BarcodeReader {
+    source: Box<dyn LineSource>,
+    credential: QrCredential,
+}
+
+impl BarcodeReader {
+    pub fn new(source: Box<dyn LineSource>, credential: QrCredential) -> Self {
+        Self { source, credential }
+    }
+
+    pub fn serial(path: impl Into<String>, baud_rate: u32, credential: QrCredential) -> Self {
+        Self::new(Box::new(SerialLines::new(path, baud_rate)), credential)
+    }
+
+    pub fn hid(path: impl Into<String>, credential: QrCredential) -> Self {
+        Self::new(Box::new(HidWedgeReader::new(path, WedgeFormat::default())), credential)
+    }
+
+    /// Turns a scanned code into a detected card, checked at Unix time `now`.
+    pub fn card_for(&self, payload: &str, now: i64) -> DetectedCard {
+        let verified = self.credential.verify(payload, now);
+        let uid = match &verified {
+            Ok(pass) => qr_uid(&pass.employee_id),
+            Err(_) => Sha256::digest(payload.as_bytes())[..QR_UID_LEN].to_vec(),
+        };
+        let mut card = DetectedCard::from_uid(uid, CardFamily::QrCode, ReaderType::Barcode);
+        card.signature_valid = Some(!matches!(verified, Err(QrError::Malformed | QrError::BadSignature)));
+        card.credential = verified.ok().map(|pass| pass.employee_id);
+        card
+    }
+}
+
+impl Reader for BarcodeReader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        self.source.open()
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.read_card(timeout).map(|card| card.uid)
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        self.init()?;
+        let start = Instant::now();
+        loop {
+            let line = self.source.read_line(timeout.saturating_sub(start.elapsed()))?;
+            if line.starts_with(QR_PREFIX) {
+                return Ok(self.card_for(&line, chrono::Utc::now().timestamp()));
+            }
+            println!("Ignoring barcode '{}' from {}", line, self.source.path());
+        }
+    }
+
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        self.init()?;
+        let mut diagnostics = ReaderDiagnostics::new(ReaderType::Barcode);
+        diagnostics.device_path = Some(self.source.path());
+        diagnostics.transport = Some(self.source.transport().to_string());
+        Ok(diagnostics)
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::Barcode
+    }
+}
@@ -0,0 +1,176 @@
+use super::ReaderType;
+
+/// Kind of credential, as far as it can be told from the anticollision data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardFamily {
+    MifareClassic1k,
+    MifareClassic4k,
+    MifareMini,
+    /// MIFARE Ultralight and NTAG21x share ATQA 0x0044 / SAK 0x00.
+    MifareUltralight,
+    MifarePlus,
+    Desfire,
+    /// Any other ISO 14443-4 target, including phones doing card emulation.
+    IsoDep,
+    /// 125 kHz EM4100/EM4102 transponder.
+    Em4100,
+    /// Sony FeliCa, identified by its NFCID2.
+    Felica,
+    /// Signed QR code read by a barcode scanner.
+    QrCode,
+    Unknown,
+}
+
+impl CardFamily {
+    /// Identifies an ISO 14443-A target from ATQA (SENS_RES) and SAK (SEL_RES), following
+    /// NXP AN10833.
+    pub fn classify(atqa: u16, sak: u8) -> Self {
+        match (sak, atqa) {
+            (0x09, _) => CardFamily::MifareMini,
+            (0x08, _) | (0x88, _) | (0x28, _) => CardFamily::MifareClassic1k,
+            (0x18, _) | (0x38, _) => CardFamily::MifareClassic4k,
+            (0x10, _) | (0x11, _) => CardFamily::MifarePlus,
+            (0x00, 0x0044) => CardFamily::MifareUltralight,
+            (0x20, 0x0344) => CardFamily::Desfire,
+            (sak, _) if sak & 0x20 != 0 => CardFamily::IsoDep,
+            _ => CardFamily::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardFamily::MifareClassic1k => "mifare_classic_1k",
+            CardFamily::MifareClassic4k => "mifare_classic_4k",
+            CardFamily::MifareMini => "mifare_mini",
+            CardFamily::MifareUltralight => "mifare_ultralight",
+            CardFamily::MifarePlus => "mifare_plus",
+            CardFamily::Desfire => "desfire",
+            CardFamily::IsoDep => "iso_dep",
+            CardFamily::Em4100 => "em4100",
+            CardFamily::Felica => "felica",
+            CardFamily::QrCode => "qr_code",
+            CardFamily::Unknown => "unknown",
+        }
+    }
+
+    pub fn is_mifare_classic(&self) -> bool {
+        matches!(
+            self,
+            CardFamily::MifareClassic1k | CardFamily::MifareClassic4k | CardFamily::MifareMini
+        )
+    }
+}
+
+/// RF technology a target was detected with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetType {
+    /// ISO/IEC 14443 type A at 106 kbps: MIFARE, NTAG, DESFire and phones.
+    Iso14443A,
+    Felica212,
+    Felica424,
+    /// ISO/IEC 14443-4 type B at 106 kbps.
+    Iso14443B,
+    /// Innovision Jewel / NFC Forum Type 1.
+    Jewel,
+}
+
+/// How a UID was allocated, per ISO/IEC 14443-3 (UID0 of a single-size UID).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UidKind {
+    /// Fixed, manufacturer-assigned UID.
+    Unique,
+    /// Fixed but not guaranteed to be unique (NUID, UID0 = xF).
+    NonUnique,
+    /// Regenerated on every activation (RID, UID0 = 0x08): phones and privacy cards.
+    Random,
+}
+
+impl UidKind {
+    /// Kind of an ISO 14443-A NFCID1. Other readers' ids carry no such marking.
+    pub fn of(uid: &[u8]) -> Self {
+        match uid {
+            [0x08, _, _, _] => UidKind::Random,
+            [first, _, _, _] if first & 0x0F == 0x0F => UidKind::NonUnique,
+            _ => UidKind::Unique,
+        }
+    }
+
+    /// A random UID identifies the tap, not the person, so it cannot be used as a credential.
+    pub fn is_ephemeral(&self) -> bool {
+        matches!(self, UidKind::Random)
+    }
+}
+
+/// Everything a reader learned about the card in the field.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DetectedCard {
+    pub uid: Vec<u8>,
+    pub uid_kind: UidKind,
+    pub atqa: Option<u16>,
+    pub sak: Option<u8>,
+    /// Answer To Select, including its length byte, for ISO 14443-4 targets.
+    pub ats: Option<Vec<u8>>,
+    pub family: CardFamily,
+    pub reader_type: ReaderType,
+    /// Identifier read from the card's memory when a credential mode asks for one.
+    pub credential: Option<String>,
+    /// Whether the NXP originality signature verified; `None` when it was not checked.
+    pub signature_valid: Option<bool>,
+    pub target_type: Option<TargetType>,
+    /// Time the poll command that found the target took to answer it.
+    pub detection_ms: Option<u64>,
+}
+
+impl DetectedCard {
+    /// A card known only by its UID, as reported by readers without anticollision data.
+    ///
+    /// Only NFC UIDs are classified: a Wiegand, EM or typed-in number that happens to start
+    /// with 0x08 is a fixed credential, not a random UID.
+    pub fn from_uid(uid: Vec<u8>, family: CardFamily, reader_type: ReaderType) -> Self {
+        let uid_kind = match reader_type {
+            ReaderType::NFC => UidKind::of(&uid),
+            _ => UidKind::Unique,
+        };
+        Self {
+            uid_kind,
+            uid,
+            atqa: None,
+            sak: None,
+            ats: None,
+            family,
+            reader_type,
+            credential: None,
+            signature_valid: None,
+            target_type: None,
+            detection_ms: None,
+        }
+    }
+}
+
+/// Parses key material as written in the configuration, e.g. "FFFFFFFFFFFF" or "ff:ff:..".
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace() && *b != b':').collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Extracts an employee number provisioned as ASCII, padded with NUL, space or 0xFF bytes.
+pub fn ascii_credential(data: &[u8]) -> Option<String> {
+    let is_padding = |b: &u8| matches!(b, 0x00 | 0x20 | 0xFF);
+    let start = data.iter().position(|b| !is_padding(b))?;
+    let end = data.iter().rposition(|b| !is_padding(b))? + 1;
+    let id = std::str::from_utf8(&data[start..end]).ok()?;
+    if id.chars().all(|c| c.is_ascii_graphic()) {
+        Some(id.to_string())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,315 @@
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use cmac::{Cmac, Mac};
+
+use super::apdu::{random_challenge, ApduChannel};
+use super::card::{ascii_credential, parse_hex};
+use super::ReaderError;
+
+/// Native DESFire commands, sent wrapped in ISO 7816-4 APDUs (CLA 0x90).
+pub const SELECT_APPLICATION: u8 = 0x5A;
+pub const AUTHENTICATE_EV2_FIRST: u8 = 0x71;
+pub const READ_DATA: u8 = 0xAD;
+pub const ADDITIONAL_FRAME: u8 = 0xAF;
+
+pub const STATUS_OK: u8 = 0x00;
+pub const STATUS_ADDITIONAL_FRAME: u8 = 0xAF;
+
+const BLOCK_LEN: usize = 16;
+/// Truncated CMACs of EV2 secure messaging are 8 bytes long.
+const MAC_LEN: usize = 8;
+/// Largest file read, so the whole encrypted answer fits in a few frames.
+const MAX_READ_LEN: u32 = 128;
+
+/// Where the employee number lives on a DESFire EV2/EV3 badge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesfireCredential {
+    pub aid: [u8; 3],
+    pub key_no: u8,
+    pub key: [u8; 16],
+    pub file_no: u8,
+    pub offset: u32,
+    pub length: u32,
+}
+
+impl DesfireCredential {
+    /// Builds the credential from configuration values; `aid_hex` is written most
+    /// significant byte first, as in the provisioning tools ("F54210").
+    pub fn new(
+        aid_hex: &str,
+        key_no: u8,
+        key_hex: &str,
+        file_no: u8,
+        offset: u32,
+        length: u32,
+    ) -> Result<Self, ReaderError> {
+        let mut aid: [u8; 3] = parse_hex(aid_hex)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| ReaderError::Initialization(format!("DESFire AID must be 3 bytes, got '{}'", aid_hex)))?;
+        aid.reverse();
+        let key: [u8; 16] = parse_hex(key_hex)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| ReaderError::Initialization("DESFire AES key must be 16 bytes".into()))?;
+        if length == 0 || length > MAX_READ_LEN {
+            return Err(ReaderError::Initialization(format!(
+                "DESFire read length must be between 1 and {} bytes",
+                MAX_READ_LEN
+            )));
+        }
+        Ok(Self {
+            aid,
+            key_no,
+            key,
+            file_no,
+            offset,
+            length,
+        })
+    }
+}
+
+/// Keys and counters of an EV2 secure messaging session.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub enc_key: [u8; 16],
+    pub mac_key: [u8; 16],
+    pub transaction_id: [u8; 4],
+    pub command_counter: u16,
+}
+
+/// Wraps a native command: 90 CMD 00 00 [Lc data] 00.
+pub fn wrap_apdu(command: u8, data: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![0x90, command, 0x00, 0x00];
+    if !data.is_empty() {
+        apdu.push(data.len() as u8);
+        apdu.extend_from_slice(data);
+    }
+    apdu.push(0x00);
+    apdu
+}
+
+/// Splits a wrapped answer into its data and DESFire status code.
+fn unwrap_response(response: &[u8]) -> Result<(&[u8], u8), ReaderError> {
+    match response {
+        [data @ .., 0x91, status] => Ok((data, *status)),
+        _ => Err(ReaderError::Protocol(format!(
+            "Unexpected DESFire response: {:02x?}",
+            response
+        ))),
+    }
+}
+
+fn status_error(command: u8, status: u8) -> ReaderError {
+    let reason = match status {
+        0x1E => "integrity error",
+        0x7E => "length error",
+        0x9D => "permission denied",
+        0xA0 => "application not found",
+        0xAE => "authentication error",
+        0xBE => "boundary error",
+        0xF0 => "file not found",
+        _ => "error",
+    };
+    ReaderError::Protocol(format!(
+        "DESFire command {:02x} failed: {} ({:02x})",
+        command, reason, status
+    ))
+}
+
+fn command(channel: &mut dyn ApduChannel, command: u8, data: &[u8]) -> Result<(Vec<u8>, u8), ReaderError> {
+    let response = channel.transceive(&wrap_apdu(command, data))?;
+    let (data, status) = unwrap_response(&response)?;
+    match status {
+        STATUS_OK | STATUS_ADDITIONAL_FRAME => Ok((data.to_vec(), status)),
+        _ => Err(status_error(command, status)),
+    }
+}
+
+pub fn select_application(channel: &mut dyn ApduChannel, aid: [u8; 3]) -> Result<(), ReaderError> {
+    command(channel, SELECT_APPLICATION, &aid).map(|_| ())
+}
+
+/// Runs AuthenticateEV2First with an AES key and derives the session keys (NXP AN12343).
+pub fn authenticate_ev2_first(
+    channel: &mut dyn ApduChannel,
+    key_no: u8,
+    key: &[u8; 16],
+) -> Result<Session, ReaderError> {
+    let (encrypted_rnd_b, status) = command(channel, AUTHENTICATE_EV2_FIRST, &[key_no, 0x00])?;
+    if status != STATUS_ADDITIONAL_FRAME || encrypted_rnd_b.len() != BLOCK_LEN {
+        return Err(ReaderError::Protocol("Unexpected AuthenticateEV2First challenge".into()));
+    }
+    let mut rnd_b = [0u8; BLOCK_LEN];
+    rnd_b.copy_from_slice(&aes_cbc_decrypt(key, &[0; BLOCK_LEN], &encrypted_rnd_b));
+    let rnd_a = random_challenge::<BLOCK_LEN>()?;
+
+    let mut token = rnd_a.to_vec();
+    token.extend_from_slice(&rotate_left(&rnd_b));
+    let (answer, status) = command(channel, ADDITIONAL_FRAME, &aes_cbc_encrypt(key, &[0; BLOCK_LEN], &token))?;
+    if status != STATUS_OK || answer.len() != 2 * BLOCK_LEN {
+        return Err(ReaderError::Protocol("Unexpected AuthenticateEV2First answer".into()));
+    }
+    let answer = aes_cbc_decrypt(key, &[0; BLOCK_LEN], &answer);
+    if answer[4..20] != rotate_left(&rnd_a) {
+        return Err(ReaderError::Protocol(
+            "DESFire card failed to prove it knows the key".into(),
+        ));
+    }
+
+    let (sv1, sv2) = session_vectors(&rnd_a, &rnd_b);
+    Ok(Session {
+        enc_key: cmac(key, &sv1),
+        mac_key: cmac(key, &sv2),
+        transaction_id: [answer[0], answer[1], answer[2], answer[3]],
+        command_counter: 0,
+    })
+}
+
+/// SV1 and SV2 of the EV2 session key derivation:
+/// label || 00 01 00 80 || RndA[15..14] || (RndA[13..8] ^ RndB[15..10]) || RndB[9..0] || RndA[7..0].
+pub fn session_vectors(rnd_a: &[u8; 16], rnd_b: &[u8; 16]) -> ([u8; 32], [u8; 32]) {
+    let mut context = [0u8; 26];
+    context[..2].copy_from_slice(&rnd_a[..2]);
+    for i in 0..6 {
+        context[2 + i] = rnd_a[2 + i] ^ rnd_b[i];
+    }
+    context[8..18].copy_from_slice(&rnd_b[6..]);
+    context[18..].copy_from_slice(&rnd_a[8..]);
+
+    let vector = |label: [u8; 2]| {
+        let mut sv = [0u8; 32];
+        sv[..6].copy_from_slice(&[label[0], label[1], 0x00, 0x01, 0x00, 0x80]);
+        sv[6..].copy_from_slice(&context);
+        sv
+    };
+    (vector([0xA5, 0x5A]), vector([0x5A, 0xA5]))
+}
+
+/// Reads `length` bytes of a data file in full (encrypted and MACed) communication mode.
+pub fn read_data_full(
+    channel: &mut dyn ApduChannel,
+    session: &mut Session,
+    file_no: u8,
+    offset: u32,
+    length: u32,
+) -> Result<Vec<u8>, ReaderError> {
+    let mut header = vec![file_no];
+    header.extend_from_slice(&offset.to_le_bytes()[..3]);
+    header.extend_from_slice(&length.to_le_bytes()[..3]);
+
+    let mut mac_input = vec![READ_DATA];
+    mac_input.extend_from_slice(&session.command_counter.to_le_bytes());
+    mac_input.extend_from_slice(&session.transaction_id);
+    mac_input.extend_from_slice(&header);
+    let mut data = header;
+    data.extend_from_slice(&truncate_mac(&cmac(&session.mac_key, &mac_input)));
+
+    let (mut answer, mut status) = command(channel, READ_DATA, &data)?;
+    while status == STATUS_ADDITIONAL_FRAME {
+        let (more, next) = command(channel, ADDITIONAL_FRAME, &[])?;
+        answer.extend_from_slice(&more);
+        status = next;
+    }
+    session.command_counter = session.command_counter.wrapping_add(1);
+
+    if answer.len() < MAC_LEN || !(answer.len() - MAC_LEN).is_multiple_of(BLOCK_LEN) {
+        return Err(ReaderError::Protocol("Malformed DESFire ReadData answer".into()));
+    }
+    let (ciphertext, mac) = answer.split_at(answer.len() - MAC_LEN);
+    let mut mac_input = vec![STATUS_OK];
+    mac_input.extend_from_slice(&session.command_counter.to_le_bytes());
+    mac_input.extend_from_slice(&session.transaction_id);
+    mac_input.extend_from_slice(ciphertext);
+    if truncate_mac(&cmac(&session.mac_key, &mac_input)) != mac {
+        return Err(ReaderError::Protocol("DESFire response MAC mismatch".into()));
+    }
+
+    let plaintext = aes_cbc_decrypt(&session.enc_key, &response_iv(session), ciphertext);
+    if plaintext.len() < length as usize {
+        return Err(ReaderError::Protocol("DESFire file shorter than requested".into()));
+    }
+    Ok(plaintext[..length as usize].to_vec())
+}
+
+/// IV of an encrypted answer: E(KSesAuthENC, 5A A5 || TI || CmdCtr || 00..00), with the
+/// counter already advanced past the command.
+fn response_iv(session: &Session) -> [u8; 16] {
+    let mut input = [0u8; 16];
+    input[..2].copy_from_slice(&[0x5A, 0xA5]);
+    input[2..6].copy_from_slice(&session.transaction_id);
+    input[6..8].copy_from_slice(&session.command_counter.to_le_bytes());
+    let encrypted = aes_cbc_encrypt(&session.enc_key, &[0; BLOCK_LEN], &input);
+    let mut iv = [0u8; 16];
+    iv.copy_from_slice(&encrypted);
+    iv
+}
+
+/// Authenticates to the configured application and returns the employee number from its file.
+pub fn read_credential(
+    channel: &mut dyn ApduChannel,
+    credential: &DesfireCredential,
+) -> Result<Option<String>, ReaderError> {
+    select_application(channel, credential.aid)?;
+    let mut session = authenticate_ev2_first(channel, credential.key_no, &credential.key)?;
+    let data = read_data_full(
+        channel,
+        &mut session,
+        credential.file_no,
+        credential.offset,
+        credential.length,
+    )?;
+    Ok(ascii_credential(&data))
+}
+
+pub fn rotate_left(block: &[u8; 16]) -> [u8; 16] {
+    let mut rotated = *block;
+    rotated.rotate_left(1);
+    rotated
+}
+
+/// AES-128-CBC over whole blocks; callers pad before encrypting.
+pub fn aes_cbc_encrypt(key: &[u8; 16], iv: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(key));
+    let mut previous = *iv;
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks(BLOCK_LEN) {
+        let mut block = GenericArray::clone_from_slice(chunk);
+        for (byte, chained) in block.iter_mut().zip(previous.iter()) {
+            *byte ^= chained;
+        }
+        cipher.encrypt_block(&mut block);
+        previous.copy_from_slice(&block);
+        output.extend_from_slice(&block);
+    }
+    output
+}
+
+pub fn aes_cbc_decrypt(key: &[u8; 16], iv: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(key));
+    let mut previous = *iv;
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks(BLOCK_LEN) {
+        let mut block = GenericArray::clone_from_slice(chunk);
+        cipher.decrypt_block(&mut block);
+        for (byte, chained) in block.iter_mut().zip(previous.iter()) {
+            *byte ^= chained;
+        }
+        previous.copy_from_slice(chunk);
+        output.extend_from_slice(&block);
+    }
+    output
+}
+
+pub fn cmac(key: &[u8; 16], data: &[u8]) -> [u8; 16] {
+    let mut mac = <Cmac<Aes128> as Mac>::new(GenericArray::from_slice(key));
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// EV2 MACs keep the odd-indexed bytes of the full CMAC.
+pub fn truncate_mac(mac: &[u8; 16]) -> [u8; 8] {
+    let mut truncated = [0u8; 8];
+    for (i, byte) in truncated.iter_mut().enumerate() {
+        *byte = mac[2 * i + 1];
+    }
+    truncated
+}
@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+use super::{ReaderError, ReaderType};
+
+/// Answer of the PN532 GetFirmwareVersion command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FirmwareVersion {
+    /// 0x32 for the PN532.
+    pub ic: u8,
+    pub version: u8,
+    pub revision: u8,
+    pub protocols: Vec<String>,
+}
+
+impl FirmwareVersion {
+    /// Parses the IC, Ver, Rev and Support bytes of a GetFirmwareVersion response.
+    pub fn parse(response: &[u8]) -> Result<Self, ReaderError> {
+        let [ic, version, revision, support] = response else {
+            return Err(ReaderError::Protocol(format!(
+                "GetFirmwareVersion returned {} bytes, expected 4",
+                response.len()
+            )));
+        };
+        let protocols = [(0x01, "ISO/IEC 14443 A"), (0x02, "ISO/IEC 14443 B"), (0x04, "ISO 18092")]
+            .iter()
+            .filter(|(bit, _)| support & bit != 0)
+            .map(|(_, name)| name.to_string())
+            .collect();
+        Ok(Self {
+            ic: *ic,
+            version: *version,
+            revision: *revision,
+            protocols,
+        })
+    }
+}
+
+/// What an administrator needs to tell a dead reader from a misconfigured one.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReaderDiagnostics {
+    pub reader_type: ReaderType,
+    pub device_path: Option<String>,
+    pub transport: Option<String>,
+    /// Only readers with a firmware to query report one.
+    pub firmware: Option<FirmwareVersion>,
+    /// Whether the chip echoed the communication-line test, when the reader supports it.
+    pub communication_test: Option<bool>,
+    pub last_error: Option<String>,
+    pub successful_reads: u64,
+    pub failed_reads: u64,
+}
+
+impl ReaderDiagnostics {
+    pub fn new(reader_type: ReaderType) -> Self {
+        Self {
+            reader_type,
+            device_path: None,
+            transport: None,
+            firmware: None,
+            communication_test: None,
+            last_error: None,
+            successful_reads: 0,
+            failed_reads: 0,
+        }
+    }
+}
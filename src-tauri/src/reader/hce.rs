@@ -0,0 +1,112 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::apdu::{command_apdu, random_challenge, split_status, ApduChannel, SW_OK};
+use super::card::parse_hex;
+use super::ReaderError;
+
+pub const CHALLENGE_LEN: usize = 16;
+pub const SIGNATURE_LEN: usize = 32;
+/// Proprietary GET TOKEN instruction of the terminal application on the phone.
+pub const INS_GET_TOKEN: u8 = 0x10;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Label the per-employee phone keys are derived under.
+const EMPLOYEE_KEY_LABEL: &[u8] = b"urniknet-phone-key:";
+
+/// The terminal application a phone emulates (HCE), and the master secret its keys come from.
+///
+/// The exchange is:
+/// 1. `00 A4 04 00 Lc AID 00` selects the application, answered with `90 00`.
+/// 2. `80 10 00 00 10 challenge 00` asks for the token, answered with
+///    `token || HMAC-SHA256(employee key, challenge || token) || 90 00`, where the token is
+///    the employee ID in UTF-8.
+///
+/// Each phone holds only the key of its own employee, see [`PhoneCredential::employee_key`],
+/// so a key pulled out of one phone cannot sign tokens for anyone else.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhoneCredential {
+    pub aid: Vec<u8>,
+    pub key: Vec<u8>,
+}
+
+impl PhoneCredential {
+    pub fn new(aid_hex: &str, key_hex: &str) -> Result<Self, ReaderError> {
+        let aid = parse_hex(aid_hex)
+            .filter(|aid| (5..=16).contains(&aid.len()))
+            .ok_or_else(|| ReaderError::Initialization(format!("Invalid phone AID '{}'", aid_hex)))?;
+        let key = parse_hex(key_hex)
+            .filter(|key| key.len() >= 16)
+            .ok_or_else(|| ReaderError::Initialization("Phone token key must be at least 16 bytes".into()))?;
+        Ok(Self { aid, key })
+    }
+
+    /// The key the phone of `employee_id` signs with: HMAC-SHA256(master key, label || ID).
+    pub fn employee_key(&self, employee_id: &str) -> Vec<u8> {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(EMPLOYEE_KEY_LABEL);
+        mac.update(employee_id.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+pub fn select_application(channel: &mut dyn ApduChannel, aid: &[u8]) -> Result<(), ReaderError> {
+    let response = channel.transceive(&command_apdu(0x00, 0xA4, 0x04, 0x00, aid))?;
+    match split_status(&response)? {
+        (_, SW_OK) => Ok(()),
+        (_, sw) => Err(ReaderError::Protocol(format!(
+            "Phone refused SELECT of the terminal application: {:02x}{:02x}",
+            sw[0], sw[1]
+        ))),
+    }
+}
+
+/// Signature a phone holding `key` gives for `token` in answer to `challenge`.
+pub fn sign_token(key: &[u8], challenge: &[u8], token: &[u8]) -> Vec<u8> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(challenge);
+    mac.update(token);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Sends `challenge` and returns the employee token once it checks out against the key of
+/// the employee it names.
+pub fn request_token(
+    channel: &mut dyn ApduChannel,
+    credential: &PhoneCredential,
+    challenge: &[u8; CHALLENGE_LEN],
+) -> Result<String, ReaderError> {
+    let response = channel.transceive(&command_apdu(0x80, INS_GET_TOKEN, 0x00, 0x00, challenge))?;
+    let data = match split_status(&response)? {
+        (data, SW_OK) => data,
+        (_, sw) => {
+            return Err(ReaderError::Protocol(format!(
+                "Phone refused the token request: {:02x}{:02x}",
+                sw[0], sw[1]
+            )))
+        }
+    };
+    if data.len() <= SIGNATURE_LEN {
+        return Err(ReaderError::Protocol("Phone token answer too short".into()));
+    }
+    let (token, signature) = data.split_at(data.len() - SIGNATURE_LEN);
+    let employee_id = std::str::from_utf8(token)
+        .map(|token| token.trim().to_string())
+        .map_err(|_| ReaderError::Protocol("Phone token is not UTF-8".into()))?;
+    let key = credential.employee_key(&employee_id);
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(challenge);
+    mac.update(token);
+    mac.verify_slice(signature)
+        .map_err(|_| ReaderError::Protocol("Phone token signature does not verify".into()))?;
+    Ok(employee_id)
+}
+
+/// Selects the terminal application and runs the challenge-response.
+pub fn read_token(channel: &mut dyn ApduChannel, credential: &PhoneCredential) -> Result<Option<String>, ReaderError> {
+    select_application(channel, &credential.aid)?;
+    let challenge = random_challenge::<CHALLENGE_LEN>()?;
+    let token = request_token(channel, credential, &challenge)?;
+    Ok(Some(token).filter(|token| !token.is_empty()))
+}
@@ -0,0 +1,361 @@
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::card::parse_hex;
+use super::{CardFamily, DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
+
+/// Size of `struct input_event`, which depends on the width of `struct timeval`.
+pub const EVENT_SIZE: usize = std::mem::size_of::<libc::input_event>();
+const EV_KEY: u16 = 0x01;
+const KEY_RELEASED: i32 = 0;
+const KEY_PRESSED: i32 = 1;
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_RIGHTSHIFT: u16 = 54;
+const KEY_KPENTER: u16 = 96;
+/// _IOW('E', 0x90, int)
+const EVIOCGRAB: libc::c_ulong = 0x4004_4590;
+/// A line is typed in one burst; keys further apart than this start a new line.
+const MAX_KEY_GAP_US: i64 = 500_000;
+const READ_SLICE: Duration = Duration::from_millis(100);
+
+/// How the reader types the card number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WedgeFormat {
+    /// Decimal digits, e.g. "0012345678" for the low 32 bits of an EM4100 tag.
+    #[default]
+    Decimal,
+    /// The UID in hex.
+    Hex,
+}
+
+impl WedgeFormat {
+    /// Turns a typed line into UID bytes. Decimal numbers become four big-endian bytes, or
+    /// as many as needed above 32 bits.
+    pub fn parse(&self, line: &str) -> Option<Vec<u8>> {
+        match self {
+            WedgeFormat::Decimal => {
+                if line.is_empty() || !line.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let value: u64 = line.parse().ok()?;
+                let bytes = value.to_be_bytes();
+                let skip = (value.leading_zeros() / 8).min(4) as usize;
+                Some(bytes[skip..].to_vec())
+            }
+            WedgeFormat::Hex => {
+                let padded = if line.len() % 2 == 1 { format!("0{}", line) } else { line.to_string() };
+                parse_hex(&padded).filter(|uid| !uid.is_empty())
+            }
+        }
+    }
+}
+
+/// One `struct input_event`, without the parts the decoder has no use for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    /// Event time in microseconds.
+    pub time_us: i64,
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl InputEvent {
+    pub fn key(time_us: i64, code: u16, value: i32) -> Self {
+        Self {
+            time_us,
+            kind: EV_KEY,
+            code,
+            value,
+        }
+    }
+
+    /// Decodes an event in the kernel's native layout.
+    pub fn parse(bytes: &[u8; EVENT_SIZE]) -> Self {
+        let word = (EVENT_SIZE - 8) / 2;
+        let field = |range: std::ops::Range<usize>| -> i64 {
+            match word {
+                8 => i64::from_ne_bytes(bytes[range].try_into().unwrap_or_default()),
+                _ => i32::from_ne_bytes(bytes[range].try_into().unwrap_or_default()) as i64,
+            }
+        };
+        let tail = &bytes[EVENT_SIZE - 8..];
+        Self {
+            time_us: field(0..word) * 1_000_000 + field(word..2 * word),
+            kind: u16::from_ne_bytes([tail[0], tail[1]]),
+            code: u16::from_ne_bytes([tail[2], tail[3]]),
+            value: i32::from_ne_bytes([tail[4], tail[5], tail[6], tail[7]]),
+        }
+    }
+
+    /// Encodes the event in the kernel's native layout, as a recorded stream holds it.
+    pub fn to_bytes(&self) -> [u8; EVENT_SIZE] {
+        let word = (EVENT_SIZE - 8) / 2;
+        let mut bytes = [0u8; EVENT_SIZE];
+        let seconds = self.time_us / 1_000_000;
+        let micros = self.time_us % 1_000_000;
+        if word == 8 {
+            bytes[..8].copy_from_slice(&seconds.to_ne_bytes());
+            bytes[8..16].copy_from_slice(&micros.to_ne_bytes());
+        } else {
+            bytes[..4].copy_from_slice(&(seconds as i32).to_ne_bytes());
+            bytes[4..8].copy_from_slice(&(micros as i32).to_ne_bytes());
+        }
+        bytes[EVENT_SIZE - 8..EVENT_SIZE - 6].copy_from_slice(&self.kind.to_ne_bytes());
+        bytes[EVENT_SIZE - 6..EVENT_SIZE - 4].copy_from_slice(&self.code.to_ne_bytes());
+        bytes[EVENT_SIZE - 4..].copy_from_slice(&self.value.to_ne_bytes());
+        bytes
+    }
+}
+
+/// Characters a US keyboard layout types for a key code, without and with shift.
+fn key_char(code: u16) -> Option<(char, char)> {
+    const DIGITS: &[u8] = b"1234567890";
+    const SHIFTED_DIGITS: &[u8] = b"!@#$%^&*()";
+    let chars = match code {
+        2..=11 => (DIGITS[code as usize - 2] as char, SHIFTED_DIGITS[code as usize - 2] as char),
+        12 => ('-', '_'),
+        13 => ('=', '+'),
+        16..=25 => letter(b"qwertyuiop"[code as usize - 16]),
+        26 => ('[', '{'),
+        27 => (']', '}'),
+        30..=38 => letter(b"asdfghjkl"[code as usize - 30]),
+        39 => (';', ':'),
+        40 => ('\'', '"'),
+        41 => ('`', '~'),
+        43 => ('\\', '|'),
+        44..=50 => letter(b"zxcvbnm"[code as usize - 44]),
+        51 => (',', '<'),
+        52 => ('.', '>'),
+        53 => ('/', '?'),
+        55 => ('*', '*'),
+        57 => (' ', ' '),
+        71..=73 => same(b"789"[code as usize - 71]),
+        74 => ('-', '-'),
+        75..=77 => same(b"456"[code as usize - 75]),
+        78 => ('+', '+'),
+        79..=81 => same(b"123"[code as usize - 79]),
+        82 => ('0', '0'),
+        83 => ('.', '.'),
+        98 => ('/', '/'),
+        _ => return None,
+    };
+    Some(chars)
+}
+
+fn letter(byte: u8) -> (char, char) {
+    (byte as char, byte.to_ascii_uppercase() as char)
+}
+
+fn same(byte: u8) -> (char, char) {
+    (byte as char, byte as char)
+}
+
+/// Assembles the lines a keyboard-wedge device types from its key events.
+#[derive(Debug, Default)]
+pub struct KeyDecoder {
+    line: String,
+    shift: bool,
+    last_key_us: Option<i64>,
+}
+
+impl KeyDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one event and returns the line it completed, if it was Enter after some text.
+    pub fn push(&mut self, event: &InputEvent) -> Option<String> {
+        if event.kind != EV_KEY {
+            return None;
+        }
+        if matches!(event.code, KEY_LEFTSHIFT | KEY_RIGHTSHIFT) {
+            self.shift = event.value != KEY_RELEASED;
+            return None;
+        }
+        if event.value != KEY_PRESSED {
+            return None;
+        }
+        if let Some(last) = self.last_key_us {
+            if event.time_us - last > MAX_KEY_GAP_US {
+                self.line.clear();
+            }
+        }
+        self.last_key_us = Some(event.time_us);
+        match event.code {
+            KEY_ENTER | KEY_KPENTER => {
+                let line = std::mem::take(&mut self.line);
+                (!line.is_empty()).then_some(line)
+            }
+            code => {
+                if let Some((plain, shifted)) = key_char(code) {
+                    self.line.push(if self.shift { shifted } else { plain });
+                }
+                None
+            }
+        }
+    }
+}
+
+/// USB reader that presents itself as a keyboard and types the card number followed by
+/// Enter.
+///
+/// The input device is grabbed exclusively, so the typed digits never reach the focused
+/// webview.
+pub struct HidWedgeReader {
+    path: String,
+    format: WedgeFormat,
+    grab: bool,
+    device: Option<File>,
+    decoder: KeyDecoder,
+    /// Bytes of a partially read event.
+    pending: Vec<u8>,
+    /// Events read but not decoded yet, as a line may end mid-read.
+    events: VecDeque<InputEvent>,
+}
+
+impl HidWedgeReader {
+    pub fn new(path: impl Into<String>, format: WedgeFormat) -> Self {
+        Self {
+            path: path.into(),
+            format,
+            grab: true,
+            device: None,
+            decoder: KeyDecoder::new(),
+            pending: Vec::with_capacity(EVENT_SIZE),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Reads the events from a file without grabbing it, for replaying recorded streams.
+    pub fn without_grab(mut self) -> Self {
+        self.grab = false;
+        self
+    }
+
+    /// Waits for the device to type a line and Enter, as barcode scanners in keyboard mode
+    /// do too.
+    pub fn read_line(&mut self, timeout: Duration) -> Result<String, ReaderError> {
+        self.init()?;
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            while let Some(event) = self.events.pop_front() {
+                if let Some(line) = self.decoder.push(&event) {
+                    return Ok(line);
+                }
+            }
+            self.read_events(timeout.saturating_sub(start.elapsed()).min(READ_SLICE))?;
+        }
+        Err(ReaderError::Timeout)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Waits up to `timeout` for the device to have events and queues them.
+    fn read_events(&mut self, timeout: Duration) -> Result<(), ReaderError> {
+        let device = self
+            .device
+            .as_mut()
+            .ok_or_else(|| ReaderError::Initialization("Input device not open".into()))?;
+        let mut poll = libc::pollfd {
+            fd: device.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut poll, 1, timeout.as_millis() as libc::c_int) };
+        if ready < 0 {
+            return Err(ReaderError::Device(std::io::Error::last_os_error().to_string()));
+        }
+        if ready == 0 {
+            return Ok(());
+        }
+        if poll.revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+            return Err(ReaderError::Device(format!("Input device {} went away", self.path)));
+        }
+        let mut chunk = [0u8; EVENT_SIZE * 16];
+        let count = match device.read(&mut chunk) {
+            Ok(count) => count,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => 0,
+            Err(e) => return Err(ReaderError::Device(format!("{}: {}", self.path, e))),
+        };
+        if count == 0 {
+            // End of a recorded stream; a live device never reports it.
+            thread::sleep(timeout.min(READ_SLICE));
+        }
+        self.pending.extend_from_slice(&chunk[..count]);
+        let events = self
+            .pending
+            .chunks_exact(EVENT_SIZE)
+            .map(|bytes| InputEvent::parse(bytes.try_into().unwrap_or(&[0; EVENT_SIZE])));
+        self.events.extend(events);
+        let consumed = self.pending.len() - self.pending.len() % EVENT_SIZE;
+        self.pending.drain(..consumed);
+        Ok(())
+    }
+}
+
+impl Reader for HidWedgeReader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        if self.device.is_some() {
+            return Ok(());
+        }
+        let device = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)
+            .map_err(|e| ReaderError::Initialization(format!("{}: {}", self.path, e)))?;
+        if self.grab {
+            let result = unsafe { libc::ioctl(device.as_raw_fd(), EVIOCGRAB, 1 as libc::c_int) };
+            if result < 0 {
+                return Err(ReaderError::Initialization(format!(
+                    "Failed to grab {}: {}",
+                    self.path,
+                    std::io::Error::last_os_error()
+                )));
+            }
+        }
+        self.device = Some(device);
+        self.decoder = KeyDecoder::new();
+        self.pending.clear();
+        self.events.clear();
+        Ok(())
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        let start = Instant::now();
+        loop {
+            let line = self.read_line(timeout.saturating_sub(start.elapsed()))?;
+            match self.format.parse(&line) {
+                Some(uid) => return Ok(uid),
+                None => println!("Dropping typed line '{}' from {}", line, self.path),
+            }
+        }
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let uid = self.read_uid(timeout)?;
+        Ok(DetectedCard::from_uid(uid, CardFamily::Unknown, ReaderType::RFID))
+    }
+
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        self.init()?;
+        let mut diagnostics = ReaderDiagnostics::new(ReaderType::RFID);
+        diagnostics.device_path = Some(self.path.clone());
+        diagnostics.transport = Some(String::from("evdev"));
+        Ok(diagnostics)
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::RFID
+    }
+}
@@ -1,5 +1,22 @@
+pub mod apdu;
+pub mod barcode;
+pub mod card;
+pub mod desfire;
+pub mod diagnostics;
+pub mod hce;
+pub mod hid;
+pub mod ndef;
+pub mod originality;
 pub mod pn532;
+pub mod presence;
 pub mod rdm6300;
+#[allow(clippy::module_inception)]
 mod reader;
+mod serial;
+pub mod simulated;
+pub mod supervisor;
+pub mod wiegand;
 
+pub use card::{CardFamily, DetectedCard, TargetType, UidKind};
+pub use diagnostics::{FirmwareVersion, ReaderDiagnostics};
 pub use reader::{Reader, ReaderError, ReaderType};
@@ -0,0 +1,219 @@
+use super::ReaderError;
+
+/// TLV tags of an NFC Forum Type 2 tag data area.
+const TLV_NULL: u8 = 0x00;
+const TLV_NDEF_MESSAGE: u8 = 0x03;
+const TLV_TERMINATOR: u8 = 0xFE;
+
+const TNF_WELL_KNOWN: u8 = 0x01;
+
+/// URI identifier codes of the NFC Forum URI record type definition.
+const URI_PREFIXES: [&str; 36] = [
+    "",
+    "http://www.",
+    "https://www.",
+    "http://",
+    "https://",
+    "tel:",
+    "mailto:",
+    "ftp://anonymous:anonymous@",
+    "ftp://ftp.",
+    "ftps://",
+    "sftp://",
+    "smb://",
+    "nfs://",
+    "ftp://",
+    "dav://",
+    "news:",
+    "telnet://",
+    "imap:",
+    "rtsp://",
+    "urn:",
+    "pop:",
+    "sip:",
+    "sips:",
+    "tftp:",
+    "btspp://",
+    "btl2cap://",
+    "btgoep://",
+    "tcpobex://",
+    "irdaobex://",
+    "file://",
+    "urn:epc:id:",
+    "urn:epc:tag:",
+    "urn:epc:pat:",
+    "urn:epc:raw:",
+    "urn:epc:",
+    "urn:nfc:",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub tnf: u8,
+    pub record_type: Vec<u8>,
+    pub id: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+impl Record {
+    fn is_well_known(&self, record_type: &[u8]) -> bool {
+        self.tnf == TNF_WELL_KNOWN && self.record_type == record_type
+    }
+
+    /// Text of a well-known "T" record, without its language code.
+    pub fn text(&self) -> Option<String> {
+        if !self.is_well_known(b"T") {
+            return None;
+        }
+        let (&status, rest) = self.payload.split_first()?;
+        let language_length = (status & 0x3F) as usize;
+        let text = rest.get(language_length..)?;
+        if status & 0x80 != 0 {
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).ok()
+        } else {
+            String::from_utf8(text.to_vec()).ok()
+        }
+    }
+
+    /// Full URI of a well-known "U" record, with its abbreviated prefix expanded.
+    pub fn uri(&self) -> Option<String> {
+        if !self.is_well_known(b"U") {
+            return None;
+        }
+        let (&code, rest) = self.payload.split_first()?;
+        let prefix = URI_PREFIXES.get(code as usize).copied().unwrap_or("");
+        Some(format!("{}{}", prefix, std::str::from_utf8(rest).ok()?))
+    }
+}
+
+/// Number of bytes of a Type 2 tag data area, from the capability container in page 3.
+/// Returns `None` when the tag is not NDEF formatted.
+pub fn data_area_len(capability_container: &[u8]) -> Option<usize> {
+    match capability_container {
+        [0xE1, _, size, ..] => Some(*size as usize * 8),
+        _ => None,
+    }
+}
+
+/// What the TLVs at the start of a Type 2 tag data area say about its NDEF message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlvScan {
+    /// The first NDEF message, complete.
+    Message(Vec<u8>),
+    /// A terminator or an empty NDEF message TLV: the tag holds no message.
+    NoMessage,
+    /// The data ends between TLVs without saying either, so more of it has to be read.
+    NeedMore,
+}
+
+/// Walks the TLVs of a Type 2 tag data area that may have been read only in part. A TLV
+/// cut off by the end of `data` is reported as an error.
+pub fn scan_tlvs(data: &[u8]) -> Result<TlvScan, ReaderError> {
+    let mut position = 0;
+    while position < data.len() {
+        let tag = data[position];
+        position += 1;
+        match tag {
+            TLV_NULL => continue,
+            TLV_TERMINATOR => return Ok(TlvScan::NoMessage),
+            _ => {}
+        }
+        let (length, header) = match data.get(position) {
+            Some(0xFF) if position + 2 < data.len() => (
+                u16::from_be_bytes([data[position + 1], data[position + 2]]) as usize,
+                3,
+            ),
+            Some(0xFF) | None => return Err(ReaderError::Protocol("Truncated NDEF TLV".into())),
+            Some(&length) => (length as usize, 1),
+        };
+        position += header;
+        let value = data
+            .get(position..position + length)
+            .ok_or_else(|| ReaderError::Protocol("Truncated NDEF TLV".into()))?;
+        if tag == TLV_NDEF_MESSAGE {
+            return Ok(if value.is_empty() {
+                TlvScan::NoMessage
+            } else {
+                TlvScan::Message(value.to_vec())
+            });
+        }
+        position += length;
+    }
+    Ok(TlvScan::NeedMore)
+}
+
+/// Returns the first NDEF message stored in a complete Type 2 tag data area, or `None` when
+/// the tag holds no message.
+pub fn find_message(data: &[u8]) -> Result<Option<Vec<u8>>, ReaderError> {
+    match scan_tlvs(data)? {
+        TlvScan::Message(message) => Ok(Some(message)),
+        TlvScan::NoMessage | TlvScan::NeedMore => Ok(None),
+    }
+}
+
+/// Splits an NDEF message into its records. Chunked records are not supported.
+pub fn parse_message(message: &[u8]) -> Result<Vec<Record>, ReaderError> {
+    let truncated = || ReaderError::Protocol("Truncated NDEF record".into());
+    let mut records = Vec::new();
+    let mut position = 0;
+    while position < message.len() {
+        let header = message[position];
+        if header & 0x20 != 0 {
+            return Err(ReaderError::Protocol("Chunked NDEF records are not supported".into()));
+        }
+        let short_record = header & 0x10 != 0;
+        let has_id = header & 0x08 != 0;
+        let type_length = *message.get(position + 1).ok_or_else(truncated)? as usize;
+        position += 2;
+        let payload_length = if short_record {
+            let length = *message.get(position).ok_or_else(truncated)? as usize;
+            position += 1;
+            length
+        } else {
+            let bytes = message.get(position..position + 4).ok_or_else(truncated)?;
+            position += 4;
+            u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+        };
+        let id_length = if has_id {
+            let length = *message.get(position).ok_or_else(truncated)? as usize;
+            position += 1;
+            length
+        } else {
+            0
+        };
+        let mut take = |length: usize| {
+            let field = message.get(position..position + length).ok_or_else(truncated)?;
+            position += length;
+            Ok::<_, ReaderError>(field.to_vec())
+        };
+        let record_type = take(type_length)?;
+        let id = take(id_length)?;
+        let payload = take(payload_length)?;
+        records.push(Record {
+            tnf: header & 0x07,
+            record_type,
+            id,
+            payload,
+        });
+        if header & 0x40 != 0 {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+/// The employee ID carried by an NDEF message: the first text record, else the first URI.
+pub fn credential_from_message(message: &[u8]) -> Result<Option<String>, ReaderError> {
+    let records = parse_message(message)?;
+    let credential = records
+        .iter()
+        .find_map(Record::text)
+        .or_else(|| records.iter().find_map(Record::uri))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    Ok(credential)
+}
@@ -0,0 +1,179 @@
+/// secp128r1 domain parameters (SEC 2).
+const P: u128 = 0xFFFFFFFD_FFFFFFFF_FFFFFFFF_FFFFFFFF;
+const A: u128 = 0xFFFFFFFD_FFFFFFFF_FFFFFFFF_FFFFFFFC;
+const B: u128 = 0xE87579C1_1079F43D_D824993C_2CEE5ED3;
+/// Order of the generator.
+pub const N: u128 = 0xFFFFFFFE_00000000_75A30D1B_9038A115;
+pub const GENERATOR: Point = Point::Affine {
+    x: 0x161FF752_8B899B2D_0C28607C_A52C5B86,
+    y: 0xCF5AC839_5BAFEB13_C02DA292_DDED7A83,
+};
+
+/// NXP public key for NTAG21x originality signatures, uncompressed.
+pub const NXP_NTAG21X_PUBLIC_KEY: [u8; 33] = [
+    0x04, 0x49, 0x4E, 0x1A, 0x38, 0x6D, 0x3D, 0x3C, 0xFE, 0x3D, 0xC1, 0x0E, 0x5D, 0xE6, 0x8A, 0x49,
+    0x9B, 0x1C, 0x20, 0x2D, 0xB5, 0xB1, 0x32, 0x39, 0x3E, 0x89, 0xED, 0x19, 0xFE, 0x5B, 0xE8, 0xBC,
+    0x61,
+];
+
+/// NXP public key for MIFARE Ultralight EV1 originality signatures, uncompressed.
+pub const NXP_ULTRALIGHT_EV1_PUBLIC_KEY: [u8; 33] = [
+    0x04, 0x90, 0x93, 0x3B, 0xDC, 0xD6, 0xE9, 0x9B, 0x4E, 0x25, 0x5E, 0x3D, 0xA5, 0x53, 0x89, 0xA8,
+    0x27, 0x56, 0x4E, 0x11, 0x71, 0x8E, 0x01, 0x72, 0x92, 0xFA, 0xF2, 0x32, 0x26, 0xA9, 0x66, 0x14,
+    0xB8,
+];
+
+/// Every key a Type 2 tag answering READ_SIG may have been signed with. Trying each is
+/// cheaper than asking GET_VERSION which chip it is.
+pub const NXP_PUBLIC_KEYS: [[u8; 33]; 2] = [NXP_NTAG21X_PUBLIC_KEY, NXP_ULTRALIGHT_EV1_PUBLIC_KEY];
+
+pub const SIGNATURE_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Point {
+    Infinity,
+    Affine { x: u128, y: u128 },
+}
+
+impl Point {
+    /// Parses an uncompressed SEC 1 point (04 || X || Y) and checks it lies on the curve.
+    pub fn from_sec1(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 33 || bytes[0] != 0x04 {
+            return None;
+        }
+        let x = u128::from_be_bytes(bytes[1..17].try_into().ok()?);
+        let y = u128::from_be_bytes(bytes[17..33].try_into().ok()?);
+        let point = Point::Affine { x, y };
+        point.is_on_curve().then_some(point)
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        match *self {
+            Point::Infinity => true,
+            Point::Affine { x, y } => {
+                if x >= P || y >= P {
+                    return false;
+                }
+                let rhs = add_mod(add_mod(mul_mod(mul_mod(x, x, P), x, P), mul_mod(A, x, P), P), B, P);
+                mul_mod(y, y, P) == rhs
+            }
+        }
+    }
+
+    pub fn add(&self, other: &Point) -> Point {
+        let (x1, y1, x2, y2) = match (*self, *other) {
+            (Point::Infinity, point) | (point, Point::Infinity) => return point,
+            (Point::Affine { x: x1, y: y1 }, Point::Affine { x: x2, y: y2 }) => (x1, y1, x2, y2),
+        };
+        let slope = if x1 == x2 {
+            if add_mod(y1, y2, P) == 0 {
+                return Point::Infinity;
+            }
+            // Tangent: (3x² + a) / 2y
+            let numerator = add_mod(mul_mod(3, mul_mod(x1, x1, P), P), A, P);
+            mul_mod(numerator, inverse(add_mod(y1, y1, P), P), P)
+        } else {
+            mul_mod(sub_mod(y2, y1, P), inverse(sub_mod(x2, x1, P), P), P)
+        };
+        let x3 = sub_mod(sub_mod(mul_mod(slope, slope, P), x1, P), x2, P);
+        let y3 = sub_mod(mul_mod(slope, sub_mod(x1, x3, P), P), y1, P);
+        Point::Affine { x: x3, y: y3 }
+    }
+
+    pub fn multiply(&self, scalar: u128) -> Point {
+        let mut result = Point::Infinity;
+        for bit in (0..128).rev() {
+            result = result.add(&result);
+            if scalar >> bit & 1 == 1 {
+                result = result.add(self);
+            }
+        }
+        result
+    }
+}
+
+/// Checks a READ_SIG answer (r || s) for `uid` against NXP's NTAG21x and Ultralight EV1
+/// keys. The signature is an ECDSA signature over the 7-byte UID on secp128r1, without
+/// hashing (NXP AN11350).
+pub fn verify(uid: &[u8], signature: &[u8]) -> bool {
+    verify_with_any_key(uid, signature, &NXP_PUBLIC_KEYS)
+}
+
+/// Whether the signature verifies against one of the uncompressed `public_keys`.
+pub fn verify_with_any_key(uid: &[u8], signature: &[u8], public_keys: &[[u8; 33]]) -> bool {
+    public_keys
+        .iter()
+        .filter_map(|key| Point::from_sec1(key))
+        .any(|public_key| verify_with_key(uid, signature, &public_key))
+}
+
+pub fn verify_with_key(uid: &[u8], signature: &[u8], public_key: &Point) -> bool {
+    if signature.len() != SIGNATURE_LEN || uid.is_empty() || uid.len() > 16 {
+        return false;
+    }
+    let r = u128::from_be_bytes(signature[..16].try_into().unwrap_or_default());
+    let s = u128::from_be_bytes(signature[16..].try_into().unwrap_or_default());
+    if r == 0 || r >= N || s == 0 || s >= N {
+        return false;
+    }
+    let e = message_scalar(uid);
+    let w = inverse(s, N);
+    let u1 = mul_mod(e, w, N);
+    let u2 = mul_mod(r, w, N);
+    match GENERATOR.multiply(u1).add(&public_key.multiply(u2)) {
+        Point::Affine { x, .. } => x % N == r,
+        Point::Infinity => false,
+    }
+}
+
+/// The unhashed message as an integer modulo the group order.
+pub fn message_scalar(uid: &[u8]) -> u128 {
+    let mut bytes = [0u8; 16];
+    bytes[16 - uid.len()..].copy_from_slice(uid);
+    u128::from_be_bytes(bytes) % N
+}
+
+pub fn add_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= modulus {
+        sum.wrapping_sub(modulus)
+    } else {
+        sum
+    }
+}
+
+pub fn sub_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    if a >= b {
+        a - b
+    } else {
+        modulus - (b - a)
+    }
+}
+
+/// Double-and-add, as the operands are too wide for a native 256-bit product.
+pub fn mul_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    let mut result = 0;
+    let a = a % modulus;
+    for bit in (0..128).rev() {
+        result = add_mod(result, result, modulus);
+        if b >> bit & 1 == 1 {
+            result = add_mod(result, a, modulus);
+        }
+    }
+    result
+}
+
+/// Inverse by Fermat's little theorem; both moduli used here are prime.
+pub fn inverse(value: u128, modulus: u128) -> u128 {
+    let mut result = 1;
+    let mut base = value % modulus;
+    let mut exponent = modulus - 2;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
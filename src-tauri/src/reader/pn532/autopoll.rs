@@ -0,0 +1,97 @@
+use serde::Deserialize;
+
+use super::parse_type_a;
+use crate::reader::{CardFamily, DetectedCard, ReaderError, ReaderType, TargetType, UidKind};
+
+/// Polls until a target answers; the host aborts the command when it stops waiting.
+const POLL_FOREVER: u8 = 0xFF;
+/// Pause between polling rounds, in units of 150 ms.
+const POLL_PERIOD: u8 = 0x01;
+
+pub const DEFAULT_POLL_TYPES: [TargetType; 3] = [
+    TargetType::Iso14443A,
+    TargetType::Felica212,
+    TargetType::Iso14443B,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanMode {
+    /// One InListPassiveTarget for type A at a time, repeated by the host.
+    #[default]
+    ListPassiveTarget,
+    /// InAutoPoll over several target types at once.
+    AutoPoll,
+}
+
+/// InAutoPoll type code for each target type.
+pub fn poll_code(target_type: TargetType) -> u8 {
+    match target_type {
+        TargetType::Iso14443A => 0x00,
+        TargetType::Felica212 => 0x11,
+        TargetType::Felica424 => 0x12,
+        TargetType::Iso14443B => 0x23,
+        TargetType::Jewel => 0x04,
+    }
+}
+
+fn target_type(code: u8) -> Option<TargetType> {
+    match code {
+        0x00 | 0x10 | 0x20 => Some(TargetType::Iso14443A),
+        0x01 | 0x11 => Some(TargetType::Felica212),
+        0x02 | 0x12 => Some(TargetType::Felica424),
+        0x03 | 0x23 => Some(TargetType::Iso14443B),
+        0x04 => Some(TargetType::Jewel),
+        _ => None,
+    }
+}
+
+/// Data of an InAutoPoll command: PollNr, Period, Type1..TypeN.
+pub fn command(poll_types: &[TargetType]) -> Vec<u8> {
+    let mut data = vec![POLL_FOREVER, POLL_PERIOD];
+    data.extend(poll_types.iter().map(|t| poll_code(*t)));
+    data
+}
+
+/// Parses an InAutoPoll response (NbTg, then Type, Len and target data per target) and
+/// returns the first target.
+pub fn parse_response(response: &[u8]) -> Result<Option<DetectedCard>, ReaderError> {
+    match response {
+        [] | [0x00, ..] => Ok(None),
+        [_, code, length, rest @ ..] if rest.len() >= *length as usize => {
+            let data = &rest[..*length as usize];
+            let target = target_type(*code).ok_or_else(|| {
+                ReaderError::Protocol(format!("Unsupported InAutoPoll target type: {:02x}", code))
+            })?;
+            parse_target(target, data).map(Some)
+        }
+        _ => Err(ReaderError::Protocol("Incomplete InAutoPoll response".into())),
+    }
+}
+
+fn parse_target(target: TargetType, data: &[u8]) -> Result<DetectedCard, ReaderError> {
+    let card = match target {
+        TargetType::Iso14443A => return parse_type_a(data),
+        // Tg, POL_RES length, response code 01, NFCID2 (8), PAD (8), [system code (2)]
+        TargetType::Felica212 | TargetType::Felica424 => match data.get(3..11) {
+            Some(nfcid2) => DetectedCard::from_uid(nfcid2.to_vec(), CardFamily::Felica, ReaderType::NFC),
+            None => return Err(ReaderError::Protocol("Incomplete FeliCa target data".into())),
+        },
+        // Tg, ATQB (0x50, PUPI (4), application data (4), protocol info (3)), ATTRIB_RES
+        TargetType::Iso14443B => match data.get(2..6) {
+            Some(pupi) if data[1] == 0x50 => DetectedCard::from_uid(pupi.to_vec(), CardFamily::IsoDep, ReaderType::NFC),
+            _ => return Err(ReaderError::Protocol("Incomplete type B target data".into())),
+        },
+        // Tg, SENS_RES (2), JEWELID (4)
+        TargetType::Jewel => match data.get(3..7) {
+            Some(id) => DetectedCard::from_uid(id.to_vec(), CardFamily::Unknown, ReaderType::NFC),
+            None => return Err(ReaderError::Protocol("Incomplete Jewel target data".into())),
+        },
+    };
+    // Only type A UIDs mark random ones; the others are taken as they are.
+    Ok(DetectedCard {
+        uid_kind: UidKind::Unique,
+        target_type: Some(target),
+        ..card
+    })
+}
@@ -0,0 +1,79 @@
+use serde::Deserialize;
+
+use crate::reader::card::{ascii_credential, parse_hex};
+use crate::reader::ReaderError;
+
+/// MIFARE commands tunnelled through InDataExchange.
+pub const MIFARE_AUTH_KEY_A: u8 = 0x60;
+pub const MIFARE_AUTH_KEY_B: u8 = 0x61;
+pub const MIFARE_READ: u8 = 0x30;
+pub const BLOCK_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    #[default]
+    A,
+    B,
+}
+
+impl KeyType {
+    pub fn auth_command(&self) -> u8 {
+        match self {
+            KeyType::A => MIFARE_AUTH_KEY_A,
+            KeyType::B => MIFARE_AUTH_KEY_B,
+        }
+    }
+}
+
+/// Where the employee number is stored on a MIFARE Classic badge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassicCredential {
+    /// Absolute block number; its sector is the one authenticated.
+    pub block: u8,
+    pub key_type: KeyType,
+    pub key: [u8; 6],
+}
+
+impl ClassicCredential {
+    /// Builds the credential from the key as written in the configuration ("FFFFFFFFFFFF").
+    pub fn new(block: u8, key_type: KeyType, key_hex: &str) -> Result<Self, ReaderError> {
+        let key: [u8; 6] = parse_hex(key_hex)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| ReaderError::Initialization(format!("MIFARE key must be 6 bytes, got '{}'", key_hex)))?;
+        if is_trailer_block(block) {
+            return Err(ReaderError::Initialization(format!(
+                "Block {} is a sector trailer and holds keys, not data",
+                block
+            )));
+        }
+        Ok(Self { block, key_type, key })
+    }
+}
+
+/// Sector trailers close every 4-block sector, and every 16-block sector above block 127
+/// on 4K cards.
+pub fn is_trailer_block(block: u8) -> bool {
+    if block < 128 {
+        block % 4 == 3
+    } else {
+        block % 16 == 15
+    }
+}
+
+/// Data of an InDataExchange authentication: command, block, key and the UID bytes the
+/// card uses for Crypto1 (the last four for double-size UIDs).
+pub fn auth_command(credential: &ClassicCredential, uid: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(12);
+    data.push(credential.key_type.auth_command());
+    data.push(credential.block);
+    data.extend_from_slice(&credential.key);
+    data.extend_from_slice(&uid[uid.len().saturating_sub(4)..]);
+    data
+}
+
+/// Extracts the employee number from a data block provisioned as ASCII, padded with
+/// NUL, space or 0xFF bytes.
+pub fn employee_id_from_block(block: &[u8]) -> Option<String> {
+    ascii_credential(block)
+}
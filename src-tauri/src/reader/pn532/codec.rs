@@ -0,0 +1,209 @@
+//! Pure encoder/decoder for PN532 host-link frames (user manual, section 6.2.1).
+//!
+//! Knows nothing about transports: callers hand in complete frames as byte slices and use
+//! [`frame_length`] to find out how many bytes make up the frame they are reading.
+
+use std::fmt::{Display, Formatter};
+
+use crate::reader::ReaderError;
+
+pub const HOST_TO_PN532: u8 = 0xD4;
+pub const PN532_TO_HOST: u8 = 0xD5;
+/// TFI of the application-level error frame the PN532 sends on a syntax error.
+pub const ERROR_TFI: u8 = 0x7F;
+
+const START: [u8; 3] = [0x00, 0x00, 0xFF];
+const ACK: [u8; 6] = [0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00];
+const NACK: [u8; 6] = [0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00];
+/// Longest payload (TFI + data) a normal information frame carries; LEN = 0xFF marks
+/// an extended frame.
+pub const MAX_NORMAL_PAYLOAD: usize = 254;
+/// Longest payload the PN532 accepts in an extended information frame.
+pub const MAX_EXTENDED_PAYLOAD: usize = 265;
+/// Bytes needed before the length of any frame is known.
+pub const HEADER_LEN: usize = 5;
+const EXTENDED_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Ack,
+    Nack,
+    /// Syntax error reported by the PN532 instead of a response.
+    Error,
+    /// Normal or extended information frame; `data` starts with the command code.
+    Information { tfi: u8, data: Vec<u8> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// Fewer bytes were supplied than the frame declares.
+    Truncated { expected: usize, actual: usize },
+    BadStartCode([u8; 3]),
+    LengthChecksum,
+    DataChecksum,
+    MissingPostamble(u8),
+    /// An information frame with no TFI byte at all.
+    EmptyPayload,
+    PayloadTooLong(usize),
+}
+
+impl FrameError {
+    /// Checksum failures are line noise and are worth a NACK; everything else is a logic error.
+    pub fn is_retransmittable(&self) -> bool {
+        matches!(self, FrameError::LengthChecksum | FrameError::DataChecksum)
+    }
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Truncated { expected, actual } => {
+                write!(f, "Truncated frame: expected {} bytes, got {}", expected, actual)
+            }
+            FrameError::BadStartCode(start) => write!(f, "Unexpected response header: {:02x?}", start),
+            FrameError::LengthChecksum => write!(f, "Invalid length checksum"),
+            FrameError::DataChecksum => write!(f, "Invalid data checksum"),
+            FrameError::MissingPostamble(byte) => write!(f, "Missing postamble (got {:02x})", byte),
+            FrameError::EmptyPayload => write!(f, "Information frame without payload"),
+            FrameError::PayloadTooLong(len) => write!(f, "Payload of {} bytes does not fit a frame", len),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<FrameError> for ReaderError {
+    fn from(error: FrameError) -> Self {
+        ReaderError::Protocol(error.to_string())
+    }
+}
+
+pub fn encode_ack() -> Vec<u8> {
+    ACK.to_vec()
+}
+
+pub fn encode_nack() -> Vec<u8> {
+    NACK.to_vec()
+}
+
+/// Encodes an information frame, switching to the extended layout when the payload
+/// (TFI + `data`) does not fit the one-byte length field.
+pub fn encode_information(tfi: u8, data: &[u8]) -> Result<Vec<u8>, FrameError> {
+    let payload_len = data.len() + 1;
+    if payload_len > MAX_EXTENDED_PAYLOAD {
+        return Err(FrameError::PayloadTooLong(payload_len));
+    }
+
+    let mut frame = Vec::with_capacity(payload_len + 10);
+    frame.extend_from_slice(&START);
+    if payload_len <= MAX_NORMAL_PAYLOAD {
+        let len = payload_len as u8;
+        frame.push(len);
+        frame.push(checksum(&[len]));
+    } else {
+        let len_m = (payload_len >> 8) as u8;
+        let len_l = payload_len as u8;
+        frame.extend_from_slice(&[0xFF, 0xFF, len_m, len_l, checksum(&[len_m, len_l])]);
+    }
+    frame.push(tfi);
+    frame.extend_from_slice(data);
+    frame.push(checksum(&frame[frame.len() - data.len() - 1..]));
+    frame.push(0x00);
+    Ok(frame)
+}
+
+pub fn encode_command(command: u8, params: &[u8]) -> Result<Vec<u8>, FrameError> {
+    let mut data = Vec::with_capacity(params.len() + 1);
+    data.push(command);
+    data.extend_from_slice(params);
+    encode_information(HOST_TO_PN532, &data)
+}
+
+/// Returns the total length of the frame starting with `prefix`, or `None` when more
+/// bytes are needed to tell (the first [`HEADER_LEN`] bytes, and three more for an
+/// extended frame).
+pub fn frame_length(prefix: &[u8]) -> Result<Option<usize>, FrameError> {
+    if prefix.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    check_start(prefix)?;
+    match (prefix[3], prefix[4]) {
+        (0x00, 0xFF) | (0xFF, 0x00) => Ok(Some(ACK.len())),
+        (0xFF, 0xFF) => {
+            if prefix.len() < EXTENDED_HEADER_LEN {
+                return Ok(None);
+            }
+            if checksum(&prefix[5..7]) != prefix[7] {
+                return Err(FrameError::LengthChecksum);
+            }
+            let len = ((prefix[5] as usize) << 8) | prefix[6] as usize;
+            Ok(Some(EXTENDED_HEADER_LEN + len + 2))
+        }
+        (len, lcs) => {
+            if len.wrapping_add(lcs) != 0 {
+                return Err(FrameError::LengthChecksum);
+            }
+            Ok(Some(HEADER_LEN + len as usize + 2))
+        }
+    }
+}
+
+/// Decodes one complete frame.
+pub fn decode(frame: &[u8]) -> Result<Frame, FrameError> {
+    let expected = frame_length(frame)?.ok_or(FrameError::Truncated {
+        expected: EXTENDED_HEADER_LEN,
+        actual: frame.len(),
+    })?;
+    if frame.len() < expected {
+        return Err(FrameError::Truncated {
+            expected,
+            actual: frame.len(),
+        });
+    }
+    let frame = &frame[..expected];
+    if frame == ACK {
+        return Ok(Frame::Ack);
+    }
+    if frame == NACK {
+        return Ok(Frame::Nack);
+    }
+
+    let body_start = if frame[3] == 0xFF && frame[4] == 0xFF {
+        EXTENDED_HEADER_LEN
+    } else {
+        HEADER_LEN
+    };
+    let payload = &frame[body_start..expected - 2];
+    let dcs = frame[expected - 2];
+    let postamble = frame[expected - 1];
+
+    if payload.is_empty() {
+        return Err(FrameError::EmptyPayload);
+    }
+    if checksum(payload) != dcs {
+        return Err(FrameError::DataChecksum);
+    }
+    if postamble != 0x00 {
+        return Err(FrameError::MissingPostamble(postamble));
+    }
+    if payload == [ERROR_TFI] {
+        return Ok(Frame::Error);
+    }
+    Ok(Frame::Information {
+        tfi: payload[0],
+        data: payload[1..].to_vec(),
+    })
+}
+
+fn check_start(prefix: &[u8]) -> Result<(), FrameError> {
+    if prefix[..3] != START {
+        return Err(FrameError::BadStartCode([prefix[0], prefix[1], prefix[2]]));
+    }
+    Ok(())
+}
+
+/// Two's complement of the byte sum, so that sum + checksum == 0 (mod 256).
+fn checksum(bytes: &[u8]) -> u8 {
+    let sum = bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+    (!sum).wrapping_add(1)
+}
@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::codec;
+use super::transport::{Transport, TransportKind};
+use crate::reader::ReaderError;
+
+/// One scripted reaction of the chip to a `wait_ready` call.
+#[derive(Debug, Clone)]
+pub enum MockStep {
+    /// The chip signals ready and the given bytes are the frame that can be read.
+    Frame(Vec<u8>),
+    /// The chip never becomes ready.
+    Timeout,
+    /// The bus fails, as an unplugged HAT would.
+    DeviceError(String),
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    script: VecDeque<MockStep>,
+    frame: Vec<u8>,
+    position: usize,
+    written: Vec<Vec<u8>>,
+    wakeups: usize,
+}
+
+/// In-memory PN532 transport that replays scripted chip frames and records host writes.
+///
+/// Clones share the same state, so a test can hand one clone to a reader and keep another
+/// to extend the script or inspect what was written.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, step: MockStep) -> &Self {
+        self.lock().script.push_back(step);
+        self
+    }
+
+    pub fn push_frame(&self, frame: Vec<u8>) -> &Self {
+        self.push(MockStep::Frame(frame))
+    }
+
+    pub fn push_ack(&self) -> &Self {
+        self.push_frame(ack_frame())
+    }
+
+    /// Queues an ACK followed by a well-formed response to `command`.
+    pub fn push_response(&self, command: u8, data: &[u8]) -> &Self {
+        self.push_ack();
+        self.push_frame(response_frame(command, data))
+    }
+
+    pub fn push_timeout(&self) -> &Self {
+        self.push(MockStep::Timeout)
+    }
+
+    /// Frames written by the host so far, oldest first.
+    pub fn written(&self) -> Vec<Vec<u8>> {
+        self.lock().written.clone()
+    }
+
+    pub fn wakeups(&self) -> usize {
+        self.lock().wakeups
+    }
+
+    /// Number of scripted steps not consumed yet.
+    pub fn remaining(&self) -> usize {
+        self.lock().script.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Transport for MockTransport {
+    fn wakeup(&mut self) -> Result<(), ReaderError> {
+        self.lock().wakeups += 1;
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), ReaderError> {
+        let mut state = self.lock();
+        state.frame.clear();
+        state.position = 0;
+        state.written.push(frame.to_vec());
+        Ok(())
+    }
+
+    fn wait_ready(&mut self, _timeout: Duration) -> Result<(), ReaderError> {
+        let mut state = self.lock();
+        match state.script.pop_front() {
+            Some(MockStep::Frame(frame)) => {
+                state.frame = frame;
+                state.position = 0;
+                Ok(())
+            }
+            Some(MockStep::DeviceError(msg)) => Err(ReaderError::Device(msg)),
+            Some(MockStep::Timeout) | None => Err(ReaderError::Timeout),
+        }
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), ReaderError> {
+        let mut state = self.lock();
+        let end = state.position + buffer.len();
+        if end > state.frame.len() {
+            return Err(ReaderError::Protocol("Read past the end of the PN532 frame".into()));
+        }
+        buffer.copy_from_slice(&state.frame[state.position..end]);
+        state.position = end;
+        Ok(())
+    }
+
+    /// Scripts hold the chip's frames, which are the same on every bus; the mock stands in
+    /// for the default I2C link.
+    fn kind(&self) -> TransportKind {
+        TransportKind::I2c
+    }
+
+    fn path(&self) -> &str {
+        "mock"
+    }
+}
+
+pub fn ack_frame() -> Vec<u8> {
+    codec::encode_ack()
+}
+
+/// Builds the frame the PN532 sends in reply to `command`.
+pub fn response_frame(command: u8, data: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(data.len() + 1);
+    payload.push(command + 1);
+    payload.extend_from_slice(data);
+    codec::encode_information(codec::PN532_TO_HOST, &payload).expect("response too long for a frame")
+}
@@ -0,0 +1,536 @@
+pub mod classic;
+pub mod autopoll;
+pub mod codec;
+#[cfg(any(test, feature = "test-support"))]
+pub mod mock;
+pub mod transport;
+
+use std::time::{Duration, Instant};
+use std::{thread, vec::Vec};
+
+use super::apdu::ApduChannel;
+use super::desfire::{self, DesfireCredential};
+use super::hce::{self, PhoneCredential};
+use super::{
+    ndef, originality, CardFamily, DetectedCard, FirmwareVersion, Reader, ReaderDiagnostics, ReaderError,
+    ReaderType, TargetType,
+};
+use autopoll::ScanMode;
+use classic::ClassicCredential;
+use codec::{Frame, FrameError};
+use transport::{I2cTransport, SpiTransport, Transport, UartTransport};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+/// NACKs sent for one response before a checksum failure is reported.
+const MAX_RETRANSMITS: usize = 2;
+pub const DEFAULT_UART_BAUD_RATE: u32 = 115_200;
+pub const DEFAULT_SPI_SPEED_HZ: u32 = 1_000_000;
+/// Bytes echoed by the Diagnose communication line test.
+pub const COMMUNICATION_TEST_PATTERN: [u8; 8] = [0x55, 0xAA, 0x00, 0xFF, 0x01, 0x02, 0x04, 0x08];
+/// Largest Type 2 tag data area read while looking for an NDEF message (NTAG216).
+const MAX_NDEF_AREA: usize = 888;
+/// Longest wait for one InListPassiveTarget answer.
+const LIST_TIMEOUT: Duration = Duration::from_millis(500);
+const EMPTY_POLL_DELAY: Duration = Duration::from_millis(100);
+
+/// Where the reader looks for the employee number once a card is detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialSource {
+    MifareClassic(ClassicCredential),
+    /// First text or URI record of the NDEF message on NTAG/Ultralight tags.
+    Ndef,
+    /// Encrypted file of an AES-authenticated DESFire EV2/EV3 application.
+    Desfire(DesfireCredential),
+}
+
+pub struct Pn532Reader {
+    transport: Box<dyn Transport>,
+    is_initialized: bool,
+    credential_source: Option<CredentialSource>,
+    phone_credential: Option<PhoneCredential>,
+    check_originality: bool,
+    scan_mode: ScanMode,
+    poll_types: Vec<TargetType>,
+    /// SEL_RES of the target activated by the last poll, until it is released.
+    active_sak: Option<u8>,
+}
+
+impl Pn532Reader {
+    pub fn new(transport: Box<dyn Transport>) -> Self {
+        Self {
+            transport,
+            is_initialized: false,
+            credential_source: None,
+            phone_credential: None,
+            check_originality: false,
+            scan_mode: ScanMode::ListPassiveTarget,
+            poll_types: autopoll::DEFAULT_POLL_TYPES.to_vec(),
+            active_sak: None,
+        }
+    }
+
+    /// Reads the employee number from `credential` whenever a MIFARE Classic card is detected.
+    pub fn with_classic_credential(mut self, credential: ClassicCredential) -> Self {
+        self.credential_source = Some(CredentialSource::MifareClassic(credential));
+        self
+    }
+
+    /// Reads the employee number from the NDEF message whenever an NTAG/Ultralight tag is
+    /// detected.
+    pub fn with_ndef_credential(mut self) -> Self {
+        self.credential_source = Some(CredentialSource::Ndef);
+        self
+    }
+
+    /// Reads the employee number from a DESFire application whenever a DESFire card is
+    /// detected.
+    pub fn with_desfire_credential(mut self, credential: DesfireCredential) -> Self {
+        self.credential_source = Some(CredentialSource::Desfire(credential));
+        self
+    }
+
+    pub fn i2c(path: impl Into<String>) -> Result<Self, ReaderError> {
+        Ok(Self::new(Box::new(I2cTransport::open(path)?)))
+    }
+
+    pub fn uart(path: &str, baud_rate: u32) -> Result<Self, ReaderError> {
+        Ok(Self::new(Box::new(UartTransport::open(path, baud_rate)?)))
+    }
+
+    pub fn spi(path: impl Into<String>, speed_hz: u32) -> Result<Self, ReaderError> {
+        Ok(Self::new(Box::new(SpiTransport::open(path, speed_hz)?)))
+    }
+
+    pub fn with_default_path() -> Result<Self, ReaderError> {
+        let default_path = std::env::var("PN532_I2C_PATH").unwrap_or_else(|_| String::from("/dev/i2c-1"));
+        Self::i2c(default_path)
+    }
+
+    fn wakeup(&mut self) -> Result<(), ReaderError> {
+        self.transport.wakeup()
+    }
+
+    fn send_command(&mut self, command: u8, data: &[u8]) -> Result<(), ReaderError> {
+        let frame = codec::encode_command(command, data)?;
+        self.transport.write_frame(&frame)
+    }
+
+    /// Reads the next complete frame from the transport, however long it turns out to be.
+    ///
+    /// Transport failures are returned as the outer error; a frame whose header does not
+    /// parse comes back as the inner [`FrameError`] so the caller can decide to NACK it.
+    fn read_frame(&mut self, timeout: Duration) -> Result<Result<Vec<u8>, FrameError>, ReaderError> {
+        self.transport.wait_ready(timeout)?;
+        let mut frame = vec![0u8; codec::HEADER_LEN];
+        self.transport.read_exact(&mut frame)?;
+        loop {
+            let read = frame.len();
+            match codec::frame_length(&frame) {
+                Ok(Some(total)) => {
+                    frame.resize(total, 0);
+                    self.transport.read_exact(&mut frame[read..])?;
+                    return Ok(Ok(frame));
+                }
+                Ok(None) => {
+                    frame.resize(read + 3, 0);
+                    self.transport.read_exact(&mut frame[read..])?;
+                }
+                Err(e) => return Ok(Err(e)),
+            }
+        }
+    }
+
+    fn read_ack(&mut self, timeout: Duration) -> Result<(), ReaderError> {
+        let frame = self.read_frame(timeout)??;
+        match codec::decode(&frame) {
+            Ok(Frame::Ack) => Ok(()),
+            _ => Err(ReaderError::Protocol(format!(
+                "Unexpected ACK frame: {:02x?}",
+                frame
+            ))),
+        }
+    }
+
+    /// Reads the response to `expected`, asking the chip to retransmit (NACK) when the frame
+    /// arrives with a bad checksum.
+    fn read_response(&mut self, expected: u8, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        let mut attempts = 0;
+        let frame = loop {
+            let decoded = self.read_frame(timeout)?.and_then(|bytes| codec::decode(&bytes));
+            match decoded {
+                Ok(frame) => break frame,
+                Err(e) if e.is_retransmittable() && attempts < MAX_RETRANSMITS => {
+                    attempts += 1;
+                    self.transport.write_frame(&codec::encode_nack())?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let (tfi, data) = match frame {
+            Frame::Information { tfi, data } => (tfi, data),
+            Frame::Error => {
+                return Err(ReaderError::Protocol(
+                    "PN532 reported a syntax error frame".into(),
+                ))
+            }
+            other => {
+                return Err(ReaderError::Protocol(format!(
+                    "Unexpected {:?} frame instead of a response",
+                    other
+                )))
+            }
+        };
+        if tfi != codec::PN532_TO_HOST {
+            return Err(ReaderError::Protocol(format!(
+                "Unexpected frame identifier: {:02x}",
+                tfi
+            )));
+        }
+        match data.first() {
+            Some(&code) if code == expected + 1 => Ok(data[1..].to_vec()),
+            Some(&code) => Err(ReaderError::Protocol(format!(
+                "Unexpected response code: {:02x}",
+                code
+            ))),
+            None => Err(ReaderError::Protocol("Response frame too short".into())),
+        }
+    }
+
+    fn configure_sam(&mut self) -> Result<(), ReaderError> {
+        self.send_command(0x14, &[0x01, 0x14, 0x01])?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        let _ = self.read_response(0x14, DEFAULT_TIMEOUT)?;
+        Ok(())
+    }
+
+    fn in_list_passive_target(&mut self, timeout: Duration) -> Result<Option<DetectedCard>, ReaderError> {
+        self.send_command(0x4A, &[0x01, 0x00])?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        let response = self.read_response(0x4A, timeout)?;
+        parse_target(&response)
+    }
+
+    /// Runs InAutoPoll until a target answers or `timeout` passes; the pending command is
+    /// then aborted with an ACK so the chip accepts the next one.
+    fn in_auto_poll(&mut self, timeout: Duration) -> Result<Option<DetectedCard>, ReaderError> {
+        self.send_command(0x60, &autopoll::command(&self.poll_types))?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        match self.read_response(0x60, timeout) {
+            Ok(response) => autopoll::parse_response(&response),
+            Err(ReaderError::Timeout) => {
+                self.transport.write_frame(&codec::encode_ack())?;
+                Err(ReaderError::Timeout)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_firmware_version(&mut self) -> Result<FirmwareVersion, ReaderError> {
+        self.send_command(0x02, &[])?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        let response = self.read_response(0x02, DEFAULT_TIMEOUT)?;
+        FirmwareVersion::parse(&response)
+    }
+
+    /// Runs the Diagnose communication line test (NumTst 0x00): the chip has to echo the
+    /// test number and the pattern sent.
+    pub fn diagnose_communication_line(&mut self) -> Result<bool, ReaderError> {
+        let mut command = vec![0x00];
+        command.extend_from_slice(&COMMUNICATION_TEST_PATTERN);
+        self.send_command(0x00, &command)?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        let response = self.read_response(0x00, DEFAULT_TIMEOUT)?;
+        Ok(response == command)
+    }
+
+    /// Runs the Diagnose card presence test (NumTst 0x06) on the activated target, which
+    /// has to be an ISO 14443-4 target.
+    pub fn diagnose_presence(&mut self) -> Result<bool, ReaderError> {
+        self.send_command(0x00, &[0x06])?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        let response = self.read_response(0x00, DEFAULT_TIMEOUT)?;
+        Ok(response.first() == Some(&0x00))
+    }
+
+    /// Releases every activated target (InRelease), so the next poll starts afresh.
+    pub fn in_release(&mut self) -> Result<(), ReaderError> {
+        self.active_sak = None;
+        self.send_command(0x52, &[0x00])?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        self.read_response(0x52, DEFAULT_TIMEOUT).map(|_| ())
+    }
+
+    /// Relays `data` to the target activated by the last poll and returns the
+    /// card's answer.
+    pub fn in_data_exchange(&mut self, data: &[u8]) -> Result<Vec<u8>, ReaderError> {
+        let mut command = Vec::with_capacity(data.len() + 1);
+        command.push(0x01);
+        command.extend_from_slice(data);
+        self.send_command(0x40, &command)?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        let response = self.read_response(0x40, DEFAULT_TIMEOUT)?;
+        match response.split_first() {
+            Some((0x00, answer)) => Ok(answer.to_vec()),
+            Some((status, _)) => Err(ReaderError::Protocol(format!(
+                "InDataExchange failed with status {:02x}",
+                status & 0x3F
+            ))),
+            None => Err(ReaderError::Protocol("Empty InDataExchange response".into())),
+        }
+    }
+
+    pub fn mifare_authenticate(&mut self, uid: &[u8], credential: &ClassicCredential) -> Result<(), ReaderError> {
+        self.in_data_exchange(&classic::auth_command(credential, uid)).map(|_| ())
+    }
+
+    /// Reads one 16-byte block of an authenticated sector.
+    pub fn mifare_read_block(&mut self, block: u8) -> Result<Vec<u8>, ReaderError> {
+        let data = self.in_data_exchange(&[classic::MIFARE_READ, block])?;
+        if data.len() < classic::BLOCK_LEN {
+            return Err(ReaderError::Protocol(format!(
+                "Short MIFARE block: {} bytes",
+                data.len()
+            )));
+        }
+        Ok(data[..classic::BLOCK_LEN].to_vec())
+    }
+
+    /// Lets the chip poll for `poll_types` itself with InAutoPoll, which answers as soon as
+    /// a target enters the field instead of on the next InListPassiveTarget round.
+    pub fn with_auto_poll(mut self, poll_types: Vec<TargetType>) -> Self {
+        self.scan_mode = ScanMode::AutoPoll;
+        if !poll_types.is_empty() {
+            self.poll_types = poll_types;
+        }
+        self
+    }
+
+    /// Asks phones emulating the terminal application for their signed employee token.
+    pub fn with_phone_credential(mut self, credential: PhoneCredential) -> Self {
+        self.phone_credential = Some(credential);
+        self
+    }
+
+    /// Verifies the NXP originality signature of every NTAG/Ultralight tag detected.
+    pub fn with_originality_check(mut self) -> Self {
+        self.check_originality = true;
+        self
+    }
+
+    /// Reads the 32-byte originality signature of an NTAG21x / Ultralight EV1 tag.
+    pub fn ntag_read_signature(&mut self) -> Result<Vec<u8>, ReaderError> {
+        let signature = self.in_data_exchange(&[0x3C, 0x00])?;
+        if signature.len() < originality::SIGNATURE_LEN {
+            return Err(ReaderError::Protocol(format!(
+                "Short originality signature: {} bytes",
+                signature.len()
+            )));
+        }
+        Ok(signature[..originality::SIGNATURE_LEN].to_vec())
+    }
+
+    /// Records whether the tag is a genuine NXP chip. Runs after the credential is read,
+    /// because a tag without READ_SIG drops out of the ACTIVE state when it refuses it.
+    fn check_signature(&mut self, card: &mut DetectedCard) -> Result<(), ReaderError> {
+        if !self.check_originality || card.family != CardFamily::MifareUltralight {
+            return Ok(());
+        }
+        let valid = match self.ntag_read_signature() {
+            Ok(signature) => originality::verify(&card.uid, &signature),
+            Err(e @ ReaderError::Device(_)) => return Err(e),
+            Err(e) => {
+                println!("Could not read the originality signature: {}", e);
+                false
+            }
+        };
+        card.signature_valid = Some(valid);
+        Ok(())
+    }
+
+    /// Reads four pages (16 bytes) of a Type 2 tag, starting at `page`.
+    pub fn ntag_read_pages(&mut self, page: u8) -> Result<Vec<u8>, ReaderError> {
+        self.mifare_read_block(page)
+    }
+
+    /// Reads the NDEF message of a Type 2 tag, or `None` when the tag carries none.
+    pub fn read_ndef_message(&mut self) -> Result<Option<Vec<u8>>, ReaderError> {
+        let capability_container = self.ntag_read_pages(3)?;
+        let area_len = match ndef::data_area_len(&capability_container) {
+            Some(len) => len.min(MAX_NDEF_AREA),
+            None => return Ok(None),
+        };
+        let mut data = Vec::with_capacity(area_len);
+        let mut page = 4u8;
+        while data.len() < area_len {
+            data.extend_from_slice(&self.ntag_read_pages(page)?);
+            page = page.wrapping_add(4);
+            // Most stickers hold a short message, so stop as soon as it is complete. Control
+            // TLVs and padding can fill the first pages, so only a terminator or the message
+            // itself ends the read; a TLV cut off at the end of `data` needs the next pages.
+            match ndef::scan_tlvs(&data) {
+                Ok(ndef::TlvScan::Message(message)) => return Ok(Some(message)),
+                Ok(ndef::TlvScan::NoMessage) => return Ok(None),
+                Ok(ndef::TlvScan::NeedMore) | Err(_) => {}
+            }
+        }
+        data.truncate(area_len);
+        ndef::find_message(&data)
+    }
+
+    /// Fills in the employee number of a badge. A card that refuses the read is still
+    /// reported, just without a credential; only bus failures are fatal.
+    fn read_credential(&mut self, card: &mut DetectedCard) -> Result<(), ReaderError> {
+        let credential = match self.credential_source.clone() {
+            Some(CredentialSource::MifareClassic(classic)) if card.family.is_mifare_classic() => self
+                .mifare_authenticate(&card.uid, &classic)
+                .and_then(|_| self.mifare_read_block(classic.block))
+                .map(|block| classic::employee_id_from_block(&block)),
+            Some(CredentialSource::Ndef) if card.family == CardFamily::MifareUltralight => self
+                .read_ndef_message()
+                .and_then(|message| message.map_or(Ok(None), |m| ndef::credential_from_message(&m))),
+            Some(CredentialSource::Desfire(credential)) if card.family == CardFamily::Desfire => {
+                desfire::read_credential(self, &credential)
+            }
+            _ => match self.phone_credential.clone() {
+                Some(phone) if card.family == CardFamily::IsoDep => hce::read_token(self, &phone),
+                _ => return Ok(()),
+            },
+        };
+        match credential {
+            Ok(credential) => card.credential = credential,
+            Err(e @ ReaderError::Device(_)) => return Err(e),
+            Err(e) => println!("Could not read the credential from the card: {}", e),
+        }
+        Ok(())
+    }
+}
+
+/// APDUs travel through InDataExchange; the PN532 handles the ISO 14443-4 block framing.
+impl ApduChannel for Pn532Reader {
+    fn transceive(&mut self, apdu: &[u8]) -> Result<Vec<u8>, ReaderError> {
+        self.in_data_exchange(apdu)
+    }
+}
+
+/// Parses an InListPassiveTarget response for one 106 kbps type A target.
+fn parse_target(response: &[u8]) -> Result<Option<DetectedCard>, ReaderError> {
+    if response.is_empty() {
+        return Ok(None);
+    }
+    let tags_found = response[0];
+    if tags_found == 0 {
+        return Ok(None);
+    }
+    if response.len() < 6 {
+        return Err(ReaderError::Protocol("Incomplete InListPassiveTarget response".into()));
+    }
+    parse_type_a(&response[1..]).map(Some)
+}
+
+/// Parses 106 kbps type A target data: Tg, SENS_RES (2), SEL_RES, NFCIDLength, NFCID1,
+/// and the ATS when present.
+fn parse_type_a(data: &[u8]) -> Result<DetectedCard, ReaderError> {
+    if data.len() < 5 {
+        return Err(ReaderError::Protocol("Incomplete type A target data".into()));
+    }
+    let atqa = u16::from_be_bytes([data[1], data[2]]);
+    let sak = data[3];
+    let uid_length = data[4] as usize;
+    if data.len() < 5 + uid_length {
+        return Err(ReaderError::Protocol("Invalid UID length in response".into()));
+    }
+    let uid = data[5..5 + uid_length].to_vec();
+    let ats = &data[5 + uid_length..];
+    let ats = if sak & 0x20 != 0 && !ats.is_empty() {
+        let ats_length = (ats[0] as usize).clamp(1, ats.len());
+        Some(ats[..ats_length].to_vec())
+    } else {
+        None
+    };
+    Ok(DetectedCard {
+        atqa: Some(atqa),
+        sak: Some(sak),
+        ats,
+        target_type: Some(TargetType::Iso14443A),
+        ..DetectedCard::from_uid(uid, CardFamily::classify(atqa, sak), ReaderType::NFC)
+    })
+}
+
+impl Reader for Pn532Reader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        if self.is_initialized {
+            return Ok(());
+        }
+        self.wakeup()?;
+        self.configure_sam()?;
+        self.is_initialized = true;
+        Ok(())
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.read_card(timeout).map(|card| card.uid)
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        self.init()?;
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            // Detection is timed per poll, so the wait for someone to present a card and
+            // the empty rounds before it do not count.
+            let polled = Instant::now();
+            let remaining = timeout.saturating_sub(start.elapsed());
+            let detected = match self.scan_mode {
+                ScanMode::ListPassiveTarget => self.in_list_passive_target(LIST_TIMEOUT.min(remaining))?,
+                ScanMode::AutoPoll => self.in_auto_poll(remaining)?,
+            };
+            match detected {
+                Some(mut card) => {
+                    self.active_sak = card.sak;
+                    card.detection_ms = Some(polled.elapsed().as_millis() as u64);
+                    self.read_credential(&mut card)?;
+                    self.check_signature(&mut card)?;
+                    return Ok(card);
+                }
+                None => thread::sleep(EMPTY_POLL_DELAY.min(timeout.saturating_sub(start.elapsed()))),
+            }
+        }
+        Err(ReaderError::Timeout)
+    }
+
+    /// Diagnose only answers for ISO 14443-4 targets; for MIFARE Classic, Ultralight and
+    /// NTAG the reader cannot tell, and the caller counts missed polls instead.
+    fn card_present(&mut self) -> Result<Option<bool>, ReaderError> {
+        self.init()?;
+        if self.active_sak.is_none_or(|sak| sak & 0x20 == 0) {
+            return Ok(None);
+        }
+        if self.diagnose_presence()? {
+            return Ok(Some(true));
+        }
+        self.in_release()?;
+        Ok(Some(false))
+    }
+
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        self.init()?;
+        let mut diagnostics = ReaderDiagnostics::new(ReaderType::NFC);
+        diagnostics.device_path = Some(self.transport.path().to_string());
+        diagnostics.transport = Some(self.transport.kind().as_str().to_string());
+        diagnostics.firmware = Some(self.get_firmware_version()?);
+        diagnostics.communication_test = Some(self.diagnose_communication_line()?);
+        Ok(diagnostics)
+    }
+
+    /// WriteGPIO with only the P3 port validated, so P7 and the interface pins are untouched.
+    fn write_gpio(&mut self, levels: u8) -> Result<(), ReaderError> {
+        self.init()?;
+        self.send_command(0x0E, &[0x80 | (levels & 0x3F), 0x00])?;
+        self.read_ack(DEFAULT_TIMEOUT)?;
+        self.read_response(0x0E, DEFAULT_TIMEOUT).map(|_| ())
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::NFC
+    }
+}
@@ -0,0 +1,387 @@
+use libc::c_ulong;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::super::serial::SerialPort;
+use crate::reader::ReaderError;
+
+const PN532_I2C_ADDRESS: u16 = 0x24;
+const I2C_SLAVE_IOCTL: c_ulong = 0x0703;
+
+/// Largest frame the PN532 can send: an extended frame with a 265-byte payload.
+const FRAME_BUFFER_LEN: usize = 8 + 265 + 2;
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+const SPI_DATA_WRITE: u8 = 0x01;
+const SPI_STATUS_READ: u8 = 0x02;
+const SPI_DATA_READ: u8 = 0x03;
+const SPI_IOC_WR_MODE: c_ulong = 0x4001_6b01;
+const SPI_IOC_WR_BITS_PER_WORD: c_ulong = 0x4001_6b03;
+const SPI_IOC_WR_MAX_SPEED_HZ: c_ulong = 0x4004_6b04;
+const SPI_IOC_MESSAGE_1: c_ulong = 0x4020_6b00;
+
+/// HSU wake-up: a long 0x55 preamble followed by idle bytes, see PN532 user manual 7.2.11.
+const HSU_WAKEUP: [u8; 16] = [
+    0x55, 0x55, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    I2c,
+    Uart,
+    Spi,
+}
+
+impl TransportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransportKind::I2c => "i2c",
+            TransportKind::Uart => "uart",
+            TransportKind::Spi => "spi",
+        }
+    }
+}
+
+/// Byte-level link to a PN532. Framing lives in [`super::Pn532Reader`]; a transport only
+/// moves complete host frames to the chip and hands back the bytes of the chip's next frame.
+pub trait Transport: Send {
+    /// Brings the chip out of power-down so it accepts the first command.
+    fn wakeup(&mut self) -> Result<(), ReaderError>;
+
+    /// Sends one complete host frame (preamble through postamble).
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), ReaderError>;
+
+    /// Blocks until the chip has a frame ready, or returns `ReaderError::Timeout`.
+    fn wait_ready(&mut self, timeout: Duration) -> Result<(), ReaderError>;
+
+    /// Reads the next bytes of the frame announced by `wait_ready`.
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), ReaderError>;
+
+    fn kind(&self) -> TransportKind;
+
+    fn path(&self) -> &str;
+}
+
+/// Frame bytes fetched in one bus transaction and handed out piecewise.
+///
+/// On I2C and SPI every read transaction restarts at the beginning of the frame, so the
+/// whole frame has to be clocked out at once even though the length is only known later.
+#[derive(Debug, Default)]
+struct FrameBuffer {
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+impl FrameBuffer {
+    fn fill(&mut self, bytes: Vec<u8>) {
+        self.bytes = bytes;
+        self.position = 0;
+    }
+
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.position = 0;
+    }
+
+    fn take(&mut self, buffer: &mut [u8]) -> Result<(), ReaderError> {
+        let end = self.position + buffer.len();
+        if end > self.bytes.len() {
+            return Err(ReaderError::Protocol("Read past the end of the PN532 frame".into()));
+        }
+        buffer.copy_from_slice(&self.bytes[self.position..end]);
+        self.position = end;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct I2cTransport {
+    device: File,
+    path: String,
+    frame: FrameBuffer,
+}
+
+impl I2cTransport {
+    pub fn open(path: impl Into<String>) -> Result<Self, ReaderError> {
+        let path: String = path.into();
+        let device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| ReaderError::Device(e.to_string()))?;
+
+        configure_slave_address(device.as_raw_fd(), PN532_I2C_ADDRESS)?;
+
+        Ok(Self {
+            device,
+            path,
+            frame: FrameBuffer::default(),
+        })
+    }
+}
+
+impl Transport for I2cTransport {
+    fn wakeup(&mut self) -> Result<(), ReaderError> {
+        self.device
+            .write(&[0x00])
+            .map_err(|e| ReaderError::Device(e.to_string()))?;
+        thread::sleep(Duration::from_millis(20));
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), ReaderError> {
+        self.frame.clear();
+        self.device
+            .write_all(frame)
+            .map_err(|e| ReaderError::Device(e.to_string()))
+    }
+
+    fn wait_ready(&mut self, timeout: Duration) -> Result<(), ReaderError> {
+        let start = Instant::now();
+        let mut status = [0u8; 1];
+        while start.elapsed() < timeout {
+            self.device
+                .read_exact(&mut status)
+                .map_err(|e| ReaderError::Device(e.to_string()))?;
+            if status[0] & 0x01 == 0x01 {
+                let mut bytes = vec![0u8; FRAME_BUFFER_LEN + 1];
+                self.device
+                    .read_exact(&mut bytes)
+                    .map_err(|e| ReaderError::Device(e.to_string()))?;
+                // Every I2C read starts with the status byte again.
+                bytes.remove(0);
+                self.frame.fill(bytes);
+                return Ok(());
+            }
+            thread::sleep(READY_POLL_INTERVAL);
+        }
+        Err(ReaderError::Timeout)
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), ReaderError> {
+        self.frame.take(buffer)
+    }
+
+    fn kind(&self) -> TransportKind {
+        TransportKind::I2c
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// High Speed UART link. The PN532 streams frames without a status byte, so readiness is
+/// simply the arrival of the first byte.
+#[derive(Debug)]
+pub struct UartTransport {
+    port: SerialPort,
+    pending: Vec<u8>,
+}
+
+impl UartTransport {
+    pub fn open(path: &str, baud_rate: u32) -> Result<Self, ReaderError> {
+        let port = SerialPort::open(path, baud_rate, Duration::from_millis(100))?;
+        Ok(Self {
+            port,
+            pending: Vec::new(),
+        })
+    }
+}
+
+impl Transport for UartTransport {
+    fn wakeup(&mut self) -> Result<(), ReaderError> {
+        self.port.write_all(&HSU_WAKEUP)?;
+        thread::sleep(Duration::from_millis(20));
+        self.port.flush_input()
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), ReaderError> {
+        self.pending.clear();
+        self.port.write_all(frame)
+    }
+
+    fn wait_ready(&mut self, timeout: Duration) -> Result<(), ReaderError> {
+        if !self.pending.is_empty() {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let mut chunk = [0u8; 64];
+        while start.elapsed() < timeout {
+            let count = self.port.read(&mut chunk)?;
+            if count > 0 {
+                self.pending.extend_from_slice(&chunk[..count]);
+                return Ok(());
+            }
+        }
+        Err(ReaderError::Timeout)
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), ReaderError> {
+        let start = Instant::now();
+        let mut chunk = [0u8; 64];
+        while self.pending.len() < buffer.len() {
+            if start.elapsed() > Duration::from_secs(1) {
+                return Err(ReaderError::Protocol("Truncated frame on UART".into()));
+            }
+            let count = self.port.read(&mut chunk)?;
+            self.pending.extend_from_slice(&chunk[..count]);
+        }
+        let rest = self.pending.split_off(buffer.len());
+        buffer.copy_from_slice(&self.pending);
+        self.pending = rest;
+        Ok(())
+    }
+
+    fn kind(&self) -> TransportKind {
+        TransportKind::Uart
+    }
+
+    fn path(&self) -> &str {
+        self.port.path()
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SpiIocTransfer {
+    tx_buf: u64,
+    rx_buf: u64,
+    len: u32,
+    speed_hz: u32,
+    delay_usecs: u16,
+    bits_per_word: u8,
+    cs_change: u8,
+    tx_nbits: u8,
+    rx_nbits: u8,
+    word_delay_usecs: u8,
+    pad: u8,
+}
+
+/// spidev link. The PN532 shifts LSB first, which many SPI controllers cannot do, so bytes
+/// are bit-reversed in software and the bus runs in plain mode 0.
+#[derive(Debug)]
+pub struct SpiTransport {
+    device: File,
+    path: String,
+    speed_hz: u32,
+    frame: FrameBuffer,
+}
+
+impl SpiTransport {
+    pub fn open(path: impl Into<String>, speed_hz: u32) -> Result<Self, ReaderError> {
+        let path: String = path.into();
+        let device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| ReaderError::Device(e.to_string()))?;
+
+        let fd = device.as_raw_fd();
+        let mode: u8 = 0;
+        let bits: u8 = 8;
+        spi_ioctl(fd, SPI_IOC_WR_MODE, &mode as *const u8 as *const libc::c_void)?;
+        spi_ioctl(fd, SPI_IOC_WR_BITS_PER_WORD, &bits as *const u8 as *const libc::c_void)?;
+        spi_ioctl(fd, SPI_IOC_WR_MAX_SPEED_HZ, &speed_hz as *const u32 as *const libc::c_void)?;
+
+        Ok(Self {
+            device,
+            path,
+            speed_hz,
+            frame: FrameBuffer::default(),
+        })
+    }
+
+    fn transfer(&mut self, tx: &[u8]) -> Result<Vec<u8>, ReaderError> {
+        let tx: Vec<u8> = tx.iter().map(|b| b.reverse_bits()).collect();
+        let mut rx = vec![0u8; tx.len()];
+        let transfer = SpiIocTransfer {
+            tx_buf: tx.as_ptr() as u64,
+            rx_buf: rx.as_mut_ptr() as u64,
+            len: tx.len() as u32,
+            speed_hz: self.speed_hz,
+            bits_per_word: 8,
+            ..Default::default()
+        };
+        spi_ioctl(
+            self.device.as_raw_fd(),
+            SPI_IOC_MESSAGE_1,
+            &transfer as *const SpiIocTransfer as *const libc::c_void,
+        )?;
+        Ok(rx.into_iter().map(|b| b.reverse_bits()).collect())
+    }
+}
+
+impl Transport for SpiTransport {
+    fn wakeup(&mut self) -> Result<(), ReaderError> {
+        // Asserting chip select is enough to wake the chip; give it time before the first command.
+        self.transfer(&[0x00])?;
+        thread::sleep(Duration::from_millis(20));
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), ReaderError> {
+        self.frame.clear();
+        let mut message = Vec::with_capacity(frame.len() + 1);
+        message.push(SPI_DATA_WRITE);
+        message.extend_from_slice(frame);
+        self.transfer(&message)?;
+        Ok(())
+    }
+
+    fn wait_ready(&mut self, timeout: Duration) -> Result<(), ReaderError> {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            let status = self.transfer(&[SPI_STATUS_READ, 0x00])?;
+            if status[1] & 0x01 == 0x01 {
+                let mut request = vec![0u8; FRAME_BUFFER_LEN + 1];
+                request[0] = SPI_DATA_READ;
+                let mut bytes = self.transfer(&request)?;
+                bytes.remove(0);
+                self.frame.fill(bytes);
+                return Ok(());
+            }
+            thread::sleep(READY_POLL_INTERVAL);
+        }
+        Err(ReaderError::Timeout)
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), ReaderError> {
+        self.frame.take(buffer)
+    }
+
+    fn kind(&self) -> TransportKind {
+        TransportKind::Spi
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+fn configure_slave_address(fd: RawFd, address: u16) -> Result<(), ReaderError> {
+    let result = unsafe { libc::ioctl(fd, I2C_SLAVE_IOCTL, address as c_ulong) };
+    if result < 0 {
+        return Err(ReaderError::Device(format!(
+            "Failed to set I2C slave address: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+fn spi_ioctl(fd: RawFd, request: c_ulong, argument: *const libc::c_void) -> Result<(), ReaderError> {
+    let result = unsafe { libc::ioctl(fd, request, argument) };
+    if result < 0 {
+        return Err(ReaderError::Device(format!(
+            "SPI ioctl {:#x} failed: {}",
+            request,
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
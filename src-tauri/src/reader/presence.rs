@@ -0,0 +1,64 @@
+/// Empty polls after which a card that was not seen again counts as removed.
+pub const DEFAULT_REMOVAL_MISSES: u32 = 2;
+
+/// Tracks which card rests in the field, so a card only counts again once it has left.
+///
+/// Readers that can check presence directly report removal through [`mark_removed`];
+/// for the others a card is gone after a number of polls in a row that did not see it.
+///
+/// [`mark_removed`]: PresenceTracker::mark_removed
+#[derive(Debug, Clone)]
+pub struct PresenceTracker {
+    current: Option<Vec<u8>>,
+    misses: u32,
+    removal_misses: u32,
+}
+
+impl Default for PresenceTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_REMOVAL_MISSES)
+    }
+}
+
+impl PresenceTracker {
+    pub fn new(removal_misses: u32) -> Self {
+        Self {
+            current: None,
+            misses: 0,
+            removal_misses: removal_misses.max(1),
+        }
+    }
+
+    /// The card currently resting in the field.
+    pub fn current(&self) -> Option<&[u8]> {
+        self.current.as_deref()
+    }
+
+    /// Records a poll that saw `uid`. Returns `true` when the card just arrived and should
+    /// be reported; a card that never left is not.
+    pub fn card_seen(&mut self, uid: &[u8]) -> bool {
+        self.misses = 0;
+        if self.current.as_deref() == Some(uid) {
+            return false;
+        }
+        self.current = Some(uid.to_vec());
+        true
+    }
+
+    /// Records a poll that saw no card. Returns the UID of the card once it counts as removed.
+    pub fn poll_empty(&mut self) -> Option<Vec<u8>> {
+        self.current.as_ref()?;
+        self.misses += 1;
+        if self.misses >= self.removal_misses {
+            self.mark_removed()
+        } else {
+            None
+        }
+    }
+
+    /// Forgets the current card, returning its UID.
+    pub fn mark_removed(&mut self) -> Option<Vec<u8>> {
+        self.misses = 0;
+        self.current.take()
+    }
+}
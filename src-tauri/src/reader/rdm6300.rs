@@ -1,23 +1,165 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use super::{Reader, ReaderError, ReaderType};
+use super::serial::SerialPort;
+use super::{CardFamily, DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
 
-pub struct Rdm6300;
+const RDM6300_BAUD_RATE: u32 = 9600;
+const FRAME_LENGTH: usize = 14;
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+const READ_SLICE: Duration = Duration::from_millis(100);
+/// The module repeats the frame roughly every 60 ms while a tag is in the field,
+/// so a gap longer than this means the tag was taken away.
+const REPEAT_WINDOW: Duration = Duration::from_millis(500);
+
+/// EM4100 125 kHz reader that streams ASCII frames over UART.
+pub struct Rdm6300 {
+    path: String,
+    port: Option<SerialPort>,
+    buffer: Vec<u8>,
+    last_tag: Option<([u8; 5], Instant)>,
+}
+
+impl Rdm6300 {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            port: None,
+            buffer: Vec::with_capacity(FRAME_LENGTH),
+            last_tag: None,
+        }
+    }
+
+    pub fn with_default_path() -> Self {
+        let default_path =
+            std::env::var("RDM6300_SERIAL_PATH").unwrap_or_else(|_| String::from("/dev/serial0"));
+        Self::new(default_path)
+    }
+
+    /// Feeds received bytes into the frame buffer and returns the first complete frame.
+    ///
+    /// Anything before STX is line noise and is dropped; a frame that fails to parse
+    /// is discarded so the next STX can resynchronise the stream.
+    fn push_bytes(&mut self, bytes: &[u8]) -> Option<Result<[u8; 5], ReaderError>> {
+        let mut result = None;
+        for &byte in bytes {
+            if self.buffer.is_empty() && byte != STX {
+                continue;
+            }
+            if byte == STX && !self.buffer.is_empty() {
+                self.buffer.clear();
+            }
+            self.buffer.push(byte);
+            if self.buffer.len() == FRAME_LENGTH {
+                let parsed = parse_frame(&self.buffer);
+                self.buffer.clear();
+                if result.is_none() {
+                    result = Some(parsed);
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns `true` when the tag is the one still resting in the field.
+    fn is_repeat(&mut self, tag: [u8; 5]) -> bool {
+        let now = Instant::now();
+        let repeat = matches!(
+            self.last_tag,
+            Some((last, seen)) if last == tag && now.duration_since(seen) < REPEAT_WINDOW
+        );
+        self.last_tag = Some((tag, now));
+        repeat
+    }
+}
 
 impl Reader for Rdm6300 {
     fn init(&mut self) -> Result<(), ReaderError> {
-        Err(ReaderError::Initialization(
-            "RDM6300 reader is not implemented".into(),
-        ))
+        if self.port.is_some() {
+            return Ok(());
+        }
+        let mut port = SerialPort::open(&self.path, RDM6300_BAUD_RATE, READ_SLICE)
+            .map_err(|e| ReaderError::Initialization(e.to_string()))?;
+        port.flush_input()?;
+        self.port = Some(port);
+        Ok(())
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.init()?;
+        let start = Instant::now();
+        let mut chunk = [0u8; 32];
+        while start.elapsed() < timeout {
+            let port = self
+                .port
+                .as_mut()
+                .ok_or_else(|| ReaderError::Initialization("RDM6300 port not open".into()))?;
+            let count = port.read(&mut chunk)?;
+            if count == 0 {
+                continue;
+            }
+            match self.push_bytes(&chunk[..count]) {
+                Some(Ok(tag)) if !self.is_repeat(tag) => return Ok(tag.to_vec()),
+                Some(Err(e)) => println!("Dropping RDM6300 frame: {}", e),
+                _ => {}
+            }
+        }
+        Err(ReaderError::Timeout)
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let uid = self.read_uid(timeout)?;
+        Ok(DetectedCard::from_uid(uid, CardFamily::Em4100, ReaderType::RFID))
     }
 
-    fn read_uid(&mut self, _timeout: Duration) -> Result<Vec<u8>, ReaderError> {
-        Err(ReaderError::Device(
-            "RDM6300 reader is not available in this build".into(),
-        ))
+    /// The module has no command channel; all there is to report is the port.
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        self.init()?;
+        let mut diagnostics = ReaderDiagnostics::new(ReaderType::RFID);
+        diagnostics.device_path = Some(self.path.clone());
+        diagnostics.transport = Some(String::from("uart"));
+        Ok(diagnostics)
     }
 
     fn get_reader_type(&self) -> ReaderType {
         ReaderType::RFID
     }
 }
+
+/// Parses a 14-byte RDM6300 frame: STX, 10 ASCII hex digits of data, 2 ASCII hex
+/// digits of XOR checksum, ETX. Returns the 5 data bytes (version + 32-bit tag ID).
+pub fn parse_frame(frame: &[u8]) -> Result<[u8; 5], ReaderError> {
+    if frame.len() != FRAME_LENGTH {
+        return Err(ReaderError::Protocol(format!(
+            "Invalid RDM6300 frame length: {}",
+            frame.len()
+        )));
+    }
+    if frame[0] != STX || frame[FRAME_LENGTH - 1] != ETX {
+        return Err(ReaderError::Protocol(format!(
+            "Invalid RDM6300 frame delimiters: {:02x?}",
+            frame
+        )));
+    }
+
+    let mut tag = [0u8; 5];
+    for (index, byte) in tag.iter_mut().enumerate() {
+        *byte = parse_hex_pair(&frame[1 + index * 2..3 + index * 2])?;
+    }
+    let checksum = parse_hex_pair(&frame[11..13])?;
+    let expected = tag.iter().fold(0u8, |acc, b| acc ^ b);
+    if checksum != expected {
+        return Err(ReaderError::Protocol(format!(
+            "Invalid RDM6300 checksum: got {:02x}, expected {:02x}",
+            checksum, expected
+        )));
+    }
+    Ok(tag)
+}
+
+fn parse_hex_pair(pair: &[u8]) -> Result<u8, ReaderError> {
+    std::str::from_utf8(pair)
+        .ok()
+        .and_then(|text| u8::from_str_radix(text, 16).ok())
+        .ok_or_else(|| ReaderError::Protocol(format!("Invalid hex digits in frame: {:02x?}", pair)))
+}
@@ -2,6 +2,9 @@ use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::time::Duration;
 
+use super::card::{CardFamily, DetectedCard};
+use super::diagnostics::ReaderDiagnostics;
+
 #[derive(Debug)]
 pub enum ReaderError {
     Initialization(String),
@@ -23,6 +26,14 @@ impl Display for ReaderError {
 
 impl Error for ReaderError {}
 
+impl ReaderError {
+    /// Whether the same device can simply be asked again. Device and initialization errors
+    /// mean the handle is unusable and has to be re-opened.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ReaderError::Timeout | ReaderError::Protocol(_))
+    }
+}
+
 pub trait Reader: Send {
     /// Initializes the reader device.
     fn init(&mut self) -> Result<(), ReaderError>;
@@ -30,12 +41,42 @@ pub trait Reader: Send {
     /// Returns the unique identifier (UID) of the current tag.
     fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError>;
 
+    /// Waits for a tag and returns everything the reader learned about it.
+    ///
+    /// Readers that only see a UID can rely on the default, which cannot tell the card family.
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let uid = self.read_uid(timeout)?;
+        Ok(DetectedCard::from_uid(uid, CardFamily::Unknown, self.get_reader_type()))
+    }
+
+    /// Checks whether the card returned by the last `read_card` is still in the field.
+    ///
+    /// `None` means the reader cannot tell without polling again, so callers fall back to
+    /// counting polls that miss the card.
+    fn card_present(&mut self) -> Result<Option<bool>, ReaderError> {
+        Ok(None)
+    }
+
+    /// Reports the device and, where the hardware allows, queries its firmware and runs a
+    /// self-test. Read counters and the last error are filled in by the supervisor.
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        Ok(ReaderDiagnostics::new(self.get_reader_type()))
+    }
+
+    /// Sets the reader's own output pins (the PN532's P3 port, bit n driving P3n).
+    fn write_gpio(&mut self, _levels: u8) -> Result<(), ReaderError> {
+        Err(ReaderError::Device("This reader has no GPIO outputs".into()))
+    }
+
     /// Gets the type of the reader.
     fn get_reader_type(&self) -> ReaderType;
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
 pub enum ReaderType {
     NFC,
     RFID,
+    Wiegand,
+    Simulated,
+    Barcode,
 }
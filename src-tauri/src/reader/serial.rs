@@ -0,0 +1,145 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use super::ReaderError;
+
+/// Raw 8N1 serial port opened through termios.
+///
+/// Reads return after at most `read_timeout` with `Ok(0)` when nothing arrived, so
+/// callers can keep their own deadlines. A port that hung up, such as an unplugged USB
+/// adapter, fails with a device error instead, so the supervisor re-opens it.
+#[derive(Debug)]
+pub struct SerialPort {
+    file: File,
+    path: String,
+    read_timeout: Duration,
+}
+
+impl SerialPort {
+    pub fn open(path: &str, baud_rate: u32, read_timeout: Duration) -> Result<Self, ReaderError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(path)
+            .map_err(|e| ReaderError::Device(format!("Failed to open {}: {}", path, e)))?;
+
+        configure_raw(&file, baud_rate, read_timeout)?;
+
+        Ok(Self {
+            file,
+            path: path.to_string(),
+            read_timeout,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Reads whatever is available, returning `Ok(0)` on timeout.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ReaderError> {
+        // A timed-out read and end of file both return 0, so wait with poll to tell them apart.
+        let mut fd = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = self.read_timeout.as_millis().clamp(1, i32::MAX as u128) as libc::c_int;
+        match unsafe { libc::poll(&mut fd, 1, timeout_ms) } {
+            0 => return Ok(0),
+            ready if ready < 0 => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() == std::io::ErrorKind::Interrupted {
+                    return Ok(0);
+                }
+                return Err(ReaderError::Device(error.to_string()));
+            }
+            _ => {}
+        }
+        if fd.revents & libc::POLLIN == 0 && fd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+            return Err(ReaderError::Device(format!("{} was disconnected", self.path)));
+        }
+        match self.file.read(buffer) {
+            Ok(0) => Err(ReaderError::Device(format!("{} was disconnected", self.path))),
+            Ok(count) => Ok(count),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Ok(0),
+            Err(e) => Err(ReaderError::Device(e.to_string())),
+        }
+    }
+
+    pub fn write_all(&mut self, data: &[u8]) -> Result<(), ReaderError> {
+        self.file
+            .write_all(data)
+            .map_err(|e| ReaderError::Device(e.to_string()))?;
+        self.file
+            .flush()
+            .map_err(|e| ReaderError::Device(e.to_string()))
+    }
+
+    /// Discards any bytes received but not yet read.
+    pub fn flush_input(&mut self) -> Result<(), ReaderError> {
+        let result = unsafe { libc::tcflush(self.file.as_raw_fd(), libc::TCIFLUSH) };
+        if result < 0 {
+            return Err(ReaderError::Device(format!(
+                "Failed to flush serial input: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn configure_raw(file: &File, baud_rate: u32, read_timeout: Duration) -> Result<(), ReaderError> {
+    let fd = file.as_raw_fd();
+    let speed = baud_constant(baud_rate)?;
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut termios) } < 0 {
+        return Err(ReaderError::Device(format!(
+            "Failed to read serial attributes: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    unsafe {
+        libc::cfmakeraw(&mut termios);
+        libc::cfsetispeed(&mut termios, speed);
+        libc::cfsetospeed(&mut termios, speed);
+    }
+    termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+    termios.c_cflag &= !(libc::CSTOPB | libc::PARENB | libc::CRTSCTS);
+    // VTIME is in tenths of a second; a zero timeout would make reads block forever.
+    let tenths = (read_timeout.as_millis() / 100).clamp(1, 255) as libc::cc_t;
+    termios.c_cc[libc::VMIN] = 0;
+    termios.c_cc[libc::VTIME] = tenths;
+
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } < 0 {
+        return Err(ReaderError::Device(format!(
+            "Failed to configure serial port: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+fn baud_constant(baud_rate: u32) -> Result<libc::speed_t, ReaderError> {
+    let speed = match baud_rate {
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        other => {
+            return Err(ReaderError::Initialization(format!(
+                "Unsupported baud rate: {}",
+                other
+            )))
+        }
+    };
+    Ok(speed)
+}
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::card::parse_hex;
+use super::{CardFamily, DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
+
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/urniknet-reader.sock";
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A line written to the simulator's socket.
+///
+/// - `tap <uid>`: a card is read once and taken away again
+/// - `place <uid>`: a card is read and stays on the reader until `remove`
+/// - `remove`: the resting card is taken away
+/// - `timeout`: the next read times out straight away
+/// - `error <message>`: the next read fails with a device error, as an unplugged reader would
+///
+/// UIDs are hex, with or without colons. Every line is answered with `ok` or `error: ...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Tap(Vec<u8>),
+    Place(Vec<u8>),
+    Remove,
+    Timeout,
+    Error(String),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (word, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+        let uid = || match parse_hex(argument) {
+            Some(uid) if !uid.is_empty() => Ok(uid),
+            _ => Err(format!("invalid UID '{}'", argument)),
+        };
+        match word {
+            "tap" => uid().map(Command::Tap),
+            "place" => uid().map(Command::Place),
+            "remove" => Ok(Command::Remove),
+            "timeout" => Ok(Command::Timeout),
+            "error" if !argument.is_empty() => Ok(Command::Error(argument.to_string())),
+            "error" => Ok(Command::Error(String::from("simulated failure"))),
+            _ => Err(format!("unknown command '{}'", word)),
+        }
+    }
+}
+
+struct Client {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+}
+
+/// Reader without hardware for development and tests, driven through a Unix socket.
+///
+/// Taps go through the same worker, supervisor and punch path as a real reader.
+pub struct SimulatedReader {
+    path: PathBuf,
+    listener: Option<UnixListener>,
+    clients: Vec<Client>,
+    pending: VecDeque<Command>,
+    resting: Option<Vec<u8>>,
+}
+
+impl SimulatedReader {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            listener: None,
+            clients: Vec::new(),
+            pending: VecDeque::new(),
+            resting: None,
+        }
+    }
+
+    /// Accepts new connections and queues the commands they sent.
+    fn pump(&mut self) -> Result<(), ReaderError> {
+        let listener = self
+            .listener
+            .as_ref()
+            .ok_or_else(|| ReaderError::Initialization("Socket not bound".into()))?;
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream
+                        .set_nonblocking(true)
+                        .map_err(|e| ReaderError::Device(e.to_string()))?;
+                    self.clients.push(Client {
+                        stream,
+                        buffer: Vec::new(),
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(ReaderError::Device(format!("Failed to accept connection: {}", e))),
+            }
+        }
+        let mut commands = Vec::new();
+        self.clients.retain_mut(|client| {
+            let mut chunk = [0u8; 256];
+            let open = loop {
+                match client.stream.read(&mut chunk) {
+                    Ok(0) => break false,
+                    Ok(count) => client.buffer.extend_from_slice(&chunk[..count]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break true,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => break false,
+                }
+            };
+            while let Some(end) = client.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = client.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reply = match Command::parse(&line) {
+                    Ok(command) => {
+                        commands.push(command);
+                        String::from("ok\n")
+                    }
+                    Err(e) => format!("error: {}\n", e),
+                };
+                // The client may already be gone; its command still counts.
+                let _ = client.stream.write_all(reply.as_bytes());
+            }
+            open
+        });
+        for command in commands {
+            match command {
+                Command::Remove => self.resting = None,
+                command => self.pending.push_back(command),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SimulatedReader {
+    fn drop(&mut self) {
+        if self.listener.take().is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl Reader for SimulatedReader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        if self.listener.is_some() {
+            return Ok(());
+        }
+        // A socket left behind by a previous run would make bind fail.
+        if UnixStream::connect(&self.path).is_err() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+        let listener = UnixListener::bind(&self.path)
+            .map_err(|e| ReaderError::Initialization(format!("{}: {}", self.path.display(), e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| ReaderError::Initialization(e.to_string()))?;
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.init()?;
+        let start = Instant::now();
+        loop {
+            self.pump()?;
+            match self.pending.pop_front() {
+                Some(Command::Tap(uid)) => {
+                    self.resting = None;
+                    return Ok(uid);
+                }
+                Some(Command::Place(uid)) => {
+                    self.resting = Some(uid.clone());
+                    return Ok(uid);
+                }
+                Some(Command::Timeout) => return Err(ReaderError::Timeout),
+                Some(Command::Error(message)) => return Err(ReaderError::Device(message)),
+                Some(Command::Remove) | None => {}
+            }
+            if start.elapsed() >= timeout {
+                return Err(ReaderError::Timeout);
+            }
+            thread::sleep(POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+        }
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let uid = self.read_uid(timeout)?;
+        Ok(DetectedCard::from_uid(uid, CardFamily::Unknown, ReaderType::Simulated))
+    }
+
+    fn card_present(&mut self) -> Result<Option<bool>, ReaderError> {
+        self.init()?;
+        self.pump()?;
+        Ok(Some(self.resting.is_some()))
+    }
+
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        self.init()?;
+        let mut diagnostics = ReaderDiagnostics::new(ReaderType::Simulated);
+        diagnostics.device_path = Some(self.path.display().to_string());
+        diagnostics.transport = Some(String::from("unix"));
+        Ok(diagnostics)
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::Simulated
+    }
+}
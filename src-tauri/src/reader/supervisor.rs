@@ -0,0 +1,256 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Protocol errors in a row after which the chip is assumed to be wedged and is re-opened.
+const MAX_CONSECUTIVE_PROTOCOL_ERRORS: u32 = 3;
+
+pub type ReaderFactory = Box<dyn Fn() -> Result<Box<dyn Reader>, ReaderError> + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReaderState {
+    /// No open attempt has been made yet.
+    Starting,
+    Healthy,
+    /// The device answers, but recent reads failed with retryable errors.
+    Degraded,
+    /// The device is closed and will be re-opened after the backoff.
+    Offline,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReaderHealth {
+    pub state: ReaderState,
+    pub reader_type: ReaderType,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    pub reconnects: u32,
+    pub next_retry_at: Option<String>,
+    /// Reads since start that returned a card, and those that failed other than by timing out.
+    pub successful_reads: u64,
+    pub failed_reads: u64,
+}
+
+impl ReaderHealth {
+    pub fn new(reader_type: ReaderType) -> Self {
+        Self {
+            state: ReaderState::Starting,
+            reader_type,
+            last_error: None,
+            consecutive_failures: 0,
+            reconnects: 0,
+            next_retry_at: None,
+            successful_reads: 0,
+            failed_reads: 0,
+        }
+    }
+}
+
+/// Wraps a reader built by `factory` and rebuilds it after fatal errors.
+///
+/// Device and initialization errors drop the reader and schedule a re-open with
+/// exponential backoff; a fresh reader runs its full `init` again (for the PN532 that
+/// includes SAMConfiguration). Timeouts are normal and protocol errors are retried in place
+/// until too many arrive in a row.
+pub struct SupervisedReader {
+    factory: ReaderFactory,
+    reader_type: ReaderType,
+    reader: Option<Box<dyn Reader>>,
+    health: Arc<Mutex<ReaderHealth>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    backoff: Duration,
+    next_attempt: Option<Instant>,
+    consecutive_protocol_errors: u32,
+    has_opened: bool,
+}
+
+impl SupervisedReader {
+    pub fn new(reader_type: ReaderType, factory: ReaderFactory, health: Arc<Mutex<ReaderHealth>>) -> Self {
+        Self {
+            factory,
+            reader_type,
+            reader: None,
+            health,
+            initial_backoff: INITIAL_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+            backoff: INITIAL_BACKOFF,
+            next_attempt: None,
+            consecutive_protocol_errors: 0,
+            has_opened: false,
+        }
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self.backoff = initial;
+        self
+    }
+
+    pub fn health(&self) -> ReaderHealth {
+        lock_health(&self.health).clone()
+    }
+
+    fn ensure_open(&mut self) -> Result<&mut Box<dyn Reader>, ReaderError> {
+        if self.reader.is_none() {
+            if let Some(next_attempt) = self.next_attempt {
+                let now = Instant::now();
+                if now < next_attempt {
+                    return Err(ReaderError::Device(format!(
+                        "Reader offline, retrying in {} ms",
+                        (next_attempt - now).as_millis()
+                    )));
+                }
+            }
+
+            let mut reader = match (self.factory)() {
+                Ok(reader) => reader,
+                Err(e) => return Err(self.schedule_reopen(e)),
+            };
+            if let Err(e) = reader.init() {
+                return Err(self.schedule_reopen(e));
+            }
+
+            if self.has_opened {
+                lock_health(&self.health).reconnects += 1;
+            }
+            self.has_opened = true;
+            self.backoff = self.initial_backoff;
+            self.next_attempt = None;
+            self.consecutive_protocol_errors = 0;
+            self.reader = Some(reader);
+            self.record_success();
+        }
+        self.reader
+            .as_mut()
+            .ok_or_else(|| ReaderError::Initialization("Reader not available".into()))
+    }
+
+    /// Drops the reader and arms the backoff timer, returning `error` for the caller.
+    fn schedule_reopen(&mut self, error: ReaderError) -> ReaderError {
+        self.reader = None;
+        self.consecutive_protocol_errors = 0;
+        self.next_attempt = Some(Instant::now() + self.backoff);
+        {
+            let mut health = lock_health(&self.health);
+            health.state = ReaderState::Offline;
+            health.last_error = Some(error.to_string());
+            health.consecutive_failures += 1;
+            health.next_retry_at = Some(
+                (chrono::Utc::now()
+                    + chrono::Duration::from_std(self.backoff).unwrap_or_else(|_| chrono::Duration::zero()))
+                .to_rfc3339(),
+            );
+        }
+        println!(
+            "Card reader failed ({}), re-opening in {} ms",
+            error,
+            self.backoff.as_millis()
+        );
+        self.backoff = (self.backoff * 2).min(self.max_backoff);
+        error
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_protocol_errors = 0;
+        let mut health = lock_health(&self.health);
+        health.state = ReaderState::Healthy;
+        health.consecutive_failures = 0;
+        health.next_retry_at = None;
+    }
+
+    fn record_retryable(&mut self, error: ReaderError) -> ReaderError {
+        self.consecutive_protocol_errors += 1;
+        if self.consecutive_protocol_errors >= MAX_CONSECUTIVE_PROTOCOL_ERRORS {
+            return self.schedule_reopen(error);
+        }
+        let mut health = lock_health(&self.health);
+        health.state = ReaderState::Degraded;
+        health.last_error = Some(error.to_string());
+        health.consecutive_failures += 1;
+        error
+    }
+
+    fn count_read<T>(&self, result: &Result<T, ReaderError>) {
+        let mut health = lock_health(&self.health);
+        match result {
+            Ok(_) => health.successful_reads += 1,
+            Err(ReaderError::Timeout) => {}
+            Err(_) => health.failed_reads += 1,
+        }
+    }
+
+    fn handle_result<T>(&mut self, result: Result<T, ReaderError>) -> Result<T, ReaderError> {
+        match result {
+            Ok(value) => {
+                self.record_success();
+                Ok(value)
+            }
+            Err(ReaderError::Timeout) => {
+                self.record_success();
+                Err(ReaderError::Timeout)
+            }
+            Err(e) if e.is_retryable() => Err(self.record_retryable(e)),
+            Err(e) => Err(self.schedule_reopen(e)),
+        }
+    }
+}
+
+impl Reader for SupervisedReader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        self.ensure_open().map(|_| ())
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        let result = self.ensure_open()?.read_uid(timeout);
+        self.count_read(&result);
+        self.handle_result(result)
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let result = self.ensure_open()?.read_card(timeout);
+        self.count_read(&result);
+        self.handle_result(result)
+    }
+
+    /// Diagnostics of the wrapped reader, completed with the supervisor's counters. An
+    /// offline reader still reports its last error instead of failing.
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        let mut diagnostics = match self.ensure_open() {
+            Ok(reader) => {
+                let result = reader.diagnostics();
+                self.handle_result(result)
+                    .unwrap_or_else(|_| ReaderDiagnostics::new(self.reader_type))
+            }
+            Err(_) => ReaderDiagnostics::new(self.reader_type),
+        };
+        let health = self.health();
+        diagnostics.last_error = health.last_error;
+        diagnostics.successful_reads = health.successful_reads;
+        diagnostics.failed_reads = health.failed_reads;
+        Ok(diagnostics)
+    }
+
+    fn card_present(&mut self) -> Result<Option<bool>, ReaderError> {
+        let result = self.ensure_open()?.card_present();
+        self.handle_result(result)
+    }
+
+    fn write_gpio(&mut self, levels: u8) -> Result<(), ReaderError> {
+        let result = self.ensure_open()?.write_gpio(levels);
+        self.handle_result(result)
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        self.reader_type
+    }
+}
+
+fn lock_health(health: &Arc<Mutex<ReaderHealth>>) -> std::sync::MutexGuard<'_, ReaderHealth> {
+    health.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::time::{Duration, Instant};
+
+use super::{CardFamily, DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
+
+/// Readers pulse a bit every 1-2 ms; a pause this long ends the frame.
+pub const FRAME_GAP: Duration = Duration::from_millis(25);
+const GPIOHANDLE_REQUEST_INPUT: u32 = 1 << 0;
+const GPIOEVENT_REQUEST_FALLING_EDGE: u32 = 1 << 1;
+/// _IOWR(0xB4, 0x04, struct gpioevent_request)
+const GPIO_GET_LINEEVENT_IOCTL: libc::c_ulong = 0xC030_B404;
+/// Size of `struct gpioevent_data`: a 64-bit timestamp and the event id, padded.
+const EVENT_DATA_LEN: usize = 16;
+const CONSUMER_LABEL: &[u8] = b"urniknet-wiegand";
+
+/// The two data lines. A pulse on D0 sends a 0 bit, a pulse on D1 a 1 bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataLine {
+    D0,
+    D1,
+}
+
+/// A falling edge on a data line, with the kernel's timestamp in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub line: DataLine,
+    pub timestamp_ns: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WiegandFrame {
+    pub bit_count: usize,
+    pub facility: u32,
+    pub card_number: u32,
+}
+
+impl WiegandFrame {
+    /// Checks the parity bits of a 26- or 34-bit frame and splits the data bits into the
+    /// facility code and the card number. The leading parity bit is even over the first
+    /// half of the data, the trailing one odd over the second half.
+    pub fn decode(bits: &[bool]) -> Result<Self, ReaderError> {
+        let facility_bits = match bits.len() {
+            26 => 8,
+            34 => 16,
+            count => {
+                return Err(ReaderError::Protocol(format!(
+                    "Unsupported Wiegand frame of {} bits",
+                    count
+                )))
+            }
+        };
+        let half = bits.len() / 2;
+        let ones = |bits: &[bool]| bits.iter().filter(|bit| **bit).count();
+        if ones(&bits[..half]) % 2 != 0 || ones(&bits[half..]) % 2 != 1 {
+            return Err(ReaderError::Protocol("Wiegand parity error".into()));
+        }
+        let value = |bits: &[bool]| bits.iter().fold(0u32, |value, bit| value << 1 | *bit as u32);
+        let data = &bits[1..bits.len() - 1];
+        Ok(Self {
+            bit_count: bits.len(),
+            facility: value(&data[..facility_bits]),
+            card_number: value(&data[facility_bits..]),
+        })
+    }
+
+    /// The data bits as big-endian bytes: facility code first, then the 16-bit card number.
+    pub fn uid(&self) -> Vec<u8> {
+        let mut uid = match self.bit_count {
+            26 => vec![self.facility as u8],
+            _ => (self.facility as u16).to_be_bytes().to_vec(),
+        };
+        uid.extend_from_slice(&(self.card_number as u16).to_be_bytes());
+        uid
+    }
+}
+
+/// Collects the bits of edges until a gap of [`FRAME_GAP`] ends the frame.
+#[derive(Debug, Default)]
+pub struct FrameAssembler {
+    bits: Vec<bool>,
+    last_edge_ns: Option<u64>,
+}
+
+impl FrameAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the bit of `edge`, returning the previous frame when the edge starts a new one.
+    pub fn push(&mut self, edge: Edge) -> Option<Vec<bool>> {
+        let gap = FRAME_GAP.as_nanos() as u64;
+        let finished = match self.last_edge_ns {
+            Some(last) if edge.timestamp_ns.saturating_sub(last) > gap => self.finish(),
+            _ => None,
+        };
+        self.last_edge_ns = Some(edge.timestamp_ns);
+        self.bits.push(edge.line == DataLine::D1);
+        finished
+    }
+
+    /// Ends the current frame, as when the lines have been quiet for [`FRAME_GAP`].
+    pub fn finish(&mut self) -> Option<Vec<bool>> {
+        self.last_edge_ns = None;
+        (!self.bits.is_empty()).then(|| std::mem::take(&mut self.bits))
+    }
+}
+
+/// Where the reader gets its edges from.
+pub trait EdgeSource: Send {
+    fn open(&mut self) -> Result<(), ReaderError>;
+
+    /// Waits up to `timeout` for the next edge; `None` means the lines stayed quiet.
+    fn next_edge(&mut self, timeout: Duration) -> Result<Option<Edge>, ReaderError>;
+
+    fn describe(&self) -> String;
+}
+
+/// Replays scripted edges; an empty script behaves like quiet lines.
+impl EdgeSource for VecDeque<Edge> {
+    fn open(&mut self) -> Result<(), ReaderError> {
+        Ok(())
+    }
+
+    fn next_edge(&mut self, _timeout: Duration) -> Result<Option<Edge>, ReaderError> {
+        Ok(self.pop_front())
+    }
+
+    fn describe(&self) -> String {
+        String::from("scripted")
+    }
+}
+
+#[repr(C)]
+struct GpioEventRequest {
+    line_offset: u32,
+    handle_flags: u32,
+    event_flags: u32,
+    consumer_label: [u8; 32],
+    fd: libc::c_int,
+}
+
+/// Falling edges of D0 and D1 from a Linux GPIO character device.
+pub struct CdevEdges {
+    chip: String,
+    d0: u32,
+    d1: u32,
+    lines: Option<[File; 2]>,
+    queued: VecDeque<Edge>,
+}
+
+impl CdevEdges {
+    pub fn new(chip: impl Into<String>, d0: u32, d1: u32) -> Self {
+        Self {
+            chip: chip.into(),
+            d0,
+            d1,
+            lines: None,
+            queued: VecDeque::new(),
+        }
+    }
+
+    fn request_line(chip: &File, offset: u32) -> Result<File, ReaderError> {
+        let mut request = GpioEventRequest {
+            line_offset: offset,
+            handle_flags: GPIOHANDLE_REQUEST_INPUT,
+            event_flags: GPIOEVENT_REQUEST_FALLING_EDGE,
+            consumer_label: [0; 32],
+            fd: -1,
+        };
+        request.consumer_label[..CONSUMER_LABEL.len()].copy_from_slice(CONSUMER_LABEL);
+        let result = unsafe {
+            libc::ioctl(
+                chip.as_raw_fd(),
+                GPIO_GET_LINEEVENT_IOCTL,
+                &mut request as *mut GpioEventRequest as *mut libc::c_void,
+            )
+        };
+        if result < 0 {
+            return Err(ReaderError::Initialization(format!(
+                "Failed to request GPIO line {} for edge events: {}",
+                offset,
+                std::io::Error::last_os_error()
+            )));
+        }
+        // SAFETY: the kernel handed us a fresh descriptor that nothing else owns.
+        Ok(unsafe { File::from_raw_fd(request.fd) })
+    }
+
+    /// Reads the edge waiting on `line`.
+    fn read_edge(file: &mut File, line: DataLine, edges: &mut Vec<Edge>) -> Result<(), ReaderError> {
+        let mut data = [0u8; EVENT_DATA_LEN];
+        file.read_exact(&mut data)
+            .map_err(|e| ReaderError::Device(format!("Failed to read GPIO edge: {}", e)))?;
+        edges.push(Edge {
+            line,
+            timestamp_ns: u64::from_ne_bytes(data[..8].try_into().unwrap_or_default()),
+        });
+        Ok(())
+    }
+}
+
+impl EdgeSource for CdevEdges {
+    fn open(&mut self) -> Result<(), ReaderError> {
+        if self.lines.is_some() {
+            return Ok(());
+        }
+        let chip = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.chip)
+            .map_err(|e| ReaderError::Initialization(format!("{}: {}", self.chip, e)))?;
+        self.lines = Some([Self::request_line(&chip, self.d0)?, Self::request_line(&chip, self.d1)?]);
+        self.queued.clear();
+        Ok(())
+    }
+
+    fn next_edge(&mut self, timeout: Duration) -> Result<Option<Edge>, ReaderError> {
+        if let Some(edge) = self.queued.pop_front() {
+            return Ok(Some(edge));
+        }
+        let lines = self
+            .lines
+            .as_mut()
+            .ok_or_else(|| ReaderError::Initialization("GPIO lines not requested".into()))?;
+        let mut polls = [poll_fd(lines[0].as_raw_fd()), poll_fd(lines[1].as_raw_fd())];
+        let ready = unsafe { libc::poll(polls.as_mut_ptr(), 2, timeout.as_millis() as libc::c_int) };
+        if ready < 0 {
+            return Err(ReaderError::Device(std::io::Error::last_os_error().to_string()));
+        }
+        // Both lines may have pulsed since the last poll; hand the edges out in time order.
+        let mut edges = Vec::new();
+        for (index, line) in [DataLine::D0, DataLine::D1].into_iter().enumerate() {
+            if polls[index].revents & libc::POLLIN != 0 {
+                Self::read_edge(&mut lines[index], line, &mut edges)?;
+            }
+        }
+        edges.sort_by_key(|edge| edge.timestamp_ns);
+        self.queued.extend(edges);
+        Ok(self.queued.pop_front())
+    }
+
+    fn describe(&self) -> String {
+        format!("{} D0={} D1={}", self.chip, self.d0, self.d1)
+    }
+}
+
+fn poll_fd(fd: RawFd) -> libc::pollfd {
+    libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    }
+}
+
+/// Door reader that sends the card as a 26- or 34-bit Wiegand frame on two GPIO lines.
+pub struct WiegandReader {
+    source: Box<dyn EdgeSource>,
+    assembler: FrameAssembler,
+}
+
+impl WiegandReader {
+    pub fn new(source: Box<dyn EdgeSource>) -> Self {
+        Self {
+            source,
+            assembler: FrameAssembler::new(),
+        }
+    }
+
+    pub fn gpio(chip: impl Into<String>, d0: u32, d1: u32) -> Self {
+        Self::new(Box::new(CdevEdges::new(chip, d0, d1)))
+    }
+
+    /// Waits for the next complete frame and decodes it.
+    pub fn read_frame(&mut self, timeout: Duration) -> Result<WiegandFrame, ReaderError> {
+        self.init()?;
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            let wait = FRAME_GAP.min(timeout.saturating_sub(start.elapsed()));
+            let frame = match self.source.next_edge(wait)? {
+                Some(edge) => self.assembler.push(edge),
+                None => self.assembler.finish(),
+            };
+            if let Some(bits) = frame {
+                return WiegandFrame::decode(&bits);
+            }
+        }
+        Err(ReaderError::Timeout)
+    }
+}
+
+impl Reader for WiegandReader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        self.source.open()
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.read_frame(timeout).map(|frame| frame.uid())
+    }
+
+    fn read_card(&mut self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let uid = self.read_uid(timeout)?;
+        Ok(DetectedCard::from_uid(uid, CardFamily::Unknown, ReaderType::Wiegand))
+    }
+
+    fn diagnostics(&mut self) -> Result<ReaderDiagnostics, ReaderError> {
+        self.init()?;
+        let mut diagnostics = ReaderDiagnostics::new(ReaderType::Wiegand);
+        diagnostics.device_path = Some(self.source.describe());
+        diagnostics.transport = Some(String::from("gpio"));
+        Ok(diagnostics)
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::Wiegand
+    }
+}
@@ -0,0 +1,171 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, NamedReaderConfig, ReaderRole};
+use crate::reader::supervisor::{ReaderHealth, SupervisedReader};
+use crate::reader::{DetectedCard, Reader, ReaderDiagnostics, ReaderError, ReaderType};
+
+/// Every configured reader, in configuration order. There is always at least one.
+pub static READERS: Lazy<Vec<ReaderSlot>> = Lazy::new(|| {
+    config::CONFIG
+        .reader_configs()
+        .into_iter()
+        .map(ReaderSlot::new)
+        .collect()
+});
+
+/// Longest a poll holds a reader in one go.
+pub const POLL_SLICE: Duration = Duration::from_millis(50);
+
+/// One named reader, supervised on its own so a dead exit reader does not stop the entry.
+pub struct ReaderSlot {
+    pub name: String,
+    pub role: ReaderRole,
+    config: NamedReaderConfig,
+    health: Arc<Mutex<ReaderHealth>>,
+    reader: SharedReader<SupervisedReader>,
+}
+
+/// A reader shared by its poll thread and the punch feedback.
+///
+/// A poll holds the reader for at most [`POLL_SLICE`] at a time and steps aside while a
+/// GPIO write waits, so LED and buzzer steps keep their timing during a long poll.
+pub struct SharedReader<R> {
+    reader: Mutex<R>,
+    gpio_waiting: AtomicUsize,
+}
+
+impl<R: Reader> SharedReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Mutex::new(reader),
+            gpio_waiting: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn lock(&self) -> Result<MutexGuard<'_, R>, ReaderError> {
+        self.reader
+            .lock()
+            .map_err(|_| ReaderError::Device("Reader mutex poisoned".into()))
+    }
+
+    /// Waits up to `timeout` for a card, in slices of [`POLL_SLICE`].
+    pub fn read_card(&self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        let start = Instant::now();
+        loop {
+            while self.gpio_waiting.load(Ordering::Acquire) > 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let slice = POLL_SLICE.min(timeout.saturating_sub(start.elapsed()));
+            let result = self.lock()?.read_card(slice);
+            match result {
+                Err(ReaderError::Timeout) if start.elapsed() < timeout => continue,
+                result => return result,
+            }
+        }
+    }
+
+    pub fn write_gpio(&self, levels: u8) -> Result<(), ReaderError> {
+        self.gpio_waiting.fetch_add(1, Ordering::AcqRel);
+        let result = self.lock().and_then(|mut reader| reader.write_gpio(levels));
+        self.gpio_waiting.fetch_sub(1, Ordering::AcqRel);
+        result
+    }
+}
+
+/// Something reported per reader, tagged with the reader it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReaderReport<T> {
+    pub name: String,
+    pub role: ReaderRole,
+    #[serde(flatten)]
+    pub report: T,
+}
+
+impl ReaderSlot {
+    fn new(named: NamedReaderConfig) -> Self {
+        let reader_type = named.reader.reader_type();
+        let health = Arc::new(Mutex::new(ReaderHealth::new(reader_type)));
+        let reader_config = named.reader.clone();
+        let reader = SupervisedReader::new(
+            reader_type,
+            Box::new(move || reader_config.open(&config::CONFIG)),
+            health.clone(),
+        );
+        Self {
+            name: named.name.clone(),
+            role: named.role,
+            config: named,
+            health,
+            reader: SharedReader::new(reader),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, SupervisedReader>, ReaderError> {
+        self.reader.lock()
+    }
+
+    fn report<T>(&self, report: T) -> ReaderReport<T> {
+        ReaderReport {
+            name: self.name.clone(),
+            role: self.role,
+            report,
+        }
+    }
+
+    pub fn reader_type(&self) -> ReaderType {
+        self.config.reader.reader_type()
+    }
+
+    pub fn read_card(&self, timeout: Duration) -> Result<DetectedCard, ReaderError> {
+        self.reader.read_card(timeout)
+    }
+
+    pub fn card_present(&self) -> Result<Option<bool>, ReaderError> {
+        self.lock()?.card_present()
+    }
+
+    pub fn write_gpio(&self, levels: u8) -> Result<(), ReaderError> {
+        self.reader.write_gpio(levels)
+    }
+
+    pub fn health(&self) -> ReaderReport<ReaderHealth> {
+        let health = self
+            .health
+            .lock()
+            .map(|health| health.clone())
+            .unwrap_or_else(|poisoned| poisoned.into_inner().clone());
+        self.report(health)
+    }
+
+    /// Diagnostics of the reader, with the configured device filled in when the reader
+    /// could not be opened to ask.
+    pub fn diagnostics(&self) -> Result<ReaderReport<ReaderDiagnostics>, ReaderError> {
+        let mut diagnostics = self.lock()?.diagnostics()?;
+        if diagnostics.device_path.is_none() {
+            diagnostics.device_path = self.config.reader.path();
+        }
+        if diagnostics.transport.is_none() {
+            diagnostics.transport = Some(self.config.reader.transport_name().to_string());
+        }
+        Ok(self.report(diagnostics))
+    }
+}
+
+/// Supervisor health of every reader, in configuration order.
+pub fn health() -> Vec<ReaderReport<ReaderHealth>> {
+    READERS.iter().map(|slot| slot.health()).collect()
+}
+
+pub fn find(name: &str) -> Option<&'static ReaderSlot> {
+    READERS.iter().find(|slot| slot.name == name)
+}
+
+/// The first configured reader, used where a single reader is meant.
+pub fn primary() -> &'static ReaderSlot {
+    &READERS[0]
+}
@@ -0,0 +1,155 @@
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::reader::ReaderError;
+use crate::worker::ScannedCard;
+
+pub const SCAN_FINISHED_EVENT: &str = "scan-finished";
+
+/// Translation key returned when a scan is requested while another one is waiting.
+pub const READER_BUSY_ERROR: &str = "The card reader is busy with another scan";
+
+/// Translation key returned by a blocking scan that was cancelled.
+pub const SCAN_CANCELLED_ERROR: &str = "The scan was cancelled";
+
+/// The terminal's scan sessions. Only one can wait for a card at a time.
+pub static SCANS: Lazy<Scans> = Lazy::new(Scans::new);
+
+#[derive(Clone, serde::Serialize)]
+pub struct ScanFinishedEvent {
+    pub scan_id: u64,
+    pub uid: Option<Vec<u8>>,
+    pub reader_name: Option<String>,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+enum Wake {
+    Card(ScannedCard),
+    Cancelled,
+}
+
+struct ActiveScan {
+    id: u64,
+    wake: Sender<Wake>,
+}
+
+/// Hands cards the workers read to whoever asked for a scan.
+///
+/// Sessions never lock a reader themselves, so a scan can be cancelled at once and a
+/// second caller is turned away instead of queueing behind the first.
+pub struct Scans {
+    active: Mutex<Option<ActiveScan>>,
+    next_id: AtomicU64,
+}
+
+impl Default for Scans {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scans {
+    pub fn new() -> Self {
+        Self {
+            active: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Starts waiting for the next card, or fails with [`READER_BUSY_ERROR`] while
+    /// another session is open.
+    pub fn start(&self) -> Result<ScanSession<'_>, String> {
+        let mut active = self.active.lock().map_err(|_| READER_BUSY_ERROR.to_string())?;
+        if active.is_some() {
+            return Err(READER_BUSY_ERROR.to_string());
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (wake, receiver) = mpsc::channel();
+        *active = Some(ActiveScan { id, wake });
+        Ok(ScanSession {
+            id,
+            scans: self,
+            receiver,
+        })
+    }
+
+    /// Cancels session `id`. Returns `false` when it already finished.
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.active.lock() {
+            Ok(mut active) => match active.take_if(|scan| scan.id == id) {
+                Some(scan) => scan.wake.send(Wake::Cancelled).is_ok(),
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Gives a freshly read card to the open session, if there is one. A card taken here
+    /// belongs to that session and is not reported as a tap.
+    ///
+    /// The session is finished in the same step, so it takes at most one card and a card
+    /// offered after its wait gave up goes on as a tap instead of being lost.
+    pub fn offer(&self, card: &ScannedCard) -> bool {
+        match self.active.lock() {
+            Ok(mut active) => match active.take() {
+                Some(scan) => scan.wake.send(Wake::Card(card.clone())).is_ok(),
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// An open scan. Dropping it frees the terminal for the next one.
+pub struct ScanSession<'a> {
+    pub id: u64,
+    scans: &'a Scans,
+    receiver: Receiver<Wake>,
+}
+
+impl ScanSession<'_> {
+    /// Waits for a card. `Ok(None)` means the session was cancelled.
+    pub fn wait(&self, timeout: Duration) -> Result<Option<ScannedCard>, ReaderError> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(wake) => Ok(Self::woken(wake)),
+            Err(RecvTimeoutError::Timeout) => self.give_up(),
+            Err(RecvTimeoutError::Disconnected) => Ok(None),
+        }
+    }
+
+    /// Closes the session after a timeout. A card or cancel that got in first, between
+    /// the timeout and the lock, still counts.
+    fn give_up(&self) -> Result<Option<ScannedCard>, ReaderError> {
+        if let Ok(mut active) = self.scans.active.lock() {
+            if active.take_if(|scan| scan.id == self.id).is_some() {
+                return Err(ReaderError::Timeout);
+            }
+        }
+        match self.receiver.try_recv() {
+            Ok(wake) => Ok(Self::woken(wake)),
+            Err(TryRecvError::Empty) => Err(ReaderError::Timeout),
+            Err(TryRecvError::Disconnected) => Ok(None),
+        }
+    }
+
+    fn woken(wake: Wake) -> Option<ScannedCard> {
+        match wake {
+            Wake::Card(card) => Some(card),
+            Wake::Cancelled => None,
+        }
+    }
+}
+
+impl Drop for ScanSession<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.scans.active.lock() {
+            if active.as_ref().is_some_and(|scan| scan.id == self.id) {
+                *active = None;
+            }
+        }
+    }
+}
@@ -10,6 +10,9 @@ diesel::table! {
         is_present -> Bool,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        pin_hash -> Nullable<Text>,
+        pin_failures -> Integer,
+        pin_locked_until -> Nullable<Timestamp>,
     }
 }
 
@@ -19,6 +22,8 @@ diesel::table! {
         card_id -> Integer,
         status -> Text,
         timestamp -> Timestamp,
+        credential_type -> Nullable<Text>,
+        reader_name -> Nullable<Text>,
     }
 }
 
@@ -0,0 +1,197 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::config::ReaderRole;
+use crate::reader::presence::PresenceTracker;
+use crate::reader::{CardFamily, DetectedCard, ReaderError, ReaderType, TargetType};
+use crate::readers::{self, ReaderSlot};
+use crate::scan::SCANS;
+
+/// How long one poll waits for a card. Polls that miss a resting card count towards its
+/// removal; the reader itself is held in shorter slices.
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// How often a resting card is checked for removal, directly or by polling it again.
+const PRESENCE_INTERVAL: Duration = Duration::from_millis(200);
+/// How long the frontend has to punch a reported tap.
+pub const TAP_WINDOW: Duration = Duration::from_secs(30);
+
+pub const CARD_SCANNED_EVENT: &str = "card-scanned";
+pub const CARD_REMOVED_EVENT: &str = "card-removed";
+pub const READER_CONNECTED_EVENT: &str = "reader-connected";
+pub const READER_DISCONNECTED_EVENT: &str = "reader-disconnected";
+
+#[derive(Clone, serde::Serialize)]
+pub struct CardScannedEvent {
+    /// Handle the frontend punches the tap with; see [`Taps`].
+    pub tap_id: u64,
+    pub uid: Vec<u8>,
+    pub uid_hex: String,
+    pub reader_name: String,
+    pub reader_role: ReaderRole,
+    pub reader_type: ReaderType,
+    pub card_family: CardFamily,
+    pub target_type: Option<TargetType>,
+    pub detection_ms: Option<u64>,
+    pub timestamp: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct CardRemovedEvent {
+    pub uid_hex: String,
+    pub reader_name: String,
+    pub timestamp: String,
+}
+
+/// A card a reader reported, with the name of that reader.
+#[derive(Debug, Clone)]
+pub struct ScannedCard {
+    pub card: DetectedCard,
+    pub reader_name: String,
+}
+
+/// Taps reported through `card-scanned`, waiting for the frontend to punch them.
+pub static TAPS: Lazy<Taps> = Lazy::new(|| Taps::new(TAP_WINDOW));
+
+/// Cards the workers reported, under ids that the frontend hands back to punch them.
+///
+/// Every punch has to come from a reader: an id is issued only for a card a worker read,
+/// can be taken once, and lapses after the window.
+pub struct Taps {
+    window: Duration,
+    pending: Mutex<HashMap<u64, (ScannedCard, Instant)>>,
+    next_id: AtomicU64,
+}
+
+impl Taps {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Keeps `scanned` for the frontend and returns the id to punch it with.
+    pub fn issue(&self, scanned: ScannedCard) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.retain(|_, (_, seen)| seen.elapsed() < self.window);
+            pending.insert(id, (scanned, Instant::now()));
+        }
+        id
+    }
+
+    /// Hands out the tap issued as `id`, once, while it is still within the window.
+    pub fn take(&self, id: u64) -> Option<ScannedCard> {
+        let (scanned, seen) = self.pending.lock().ok()?.remove(&id)?;
+        (seen.elapsed() < self.window).then_some(scanned)
+    }
+}
+
+/// Starts one thread per configured reader that keeps polling it and reports taps as events.
+pub fn spawn(app: AppHandle) {
+    for slot in readers::READERS.iter() {
+        let app = app.clone();
+        thread::Builder::new()
+            .name(format!("card-reader-{}", slot.name))
+            .spawn(move || poll_loop(app, slot))
+            .expect("Failed to start card reader thread");
+    }
+}
+
+fn poll_loop(app: AppHandle, slot: &ReaderSlot) {
+    let mut connected: Option<bool> = None;
+    let mut presence = PresenceTracker::default();
+    loop {
+        if presence.current().is_some() {
+            match slot.card_present() {
+                Ok(Some(true)) => {
+                    thread::sleep(PRESENCE_INTERVAL);
+                    continue;
+                }
+                Ok(Some(false)) => {
+                    report_removed(&app, slot, presence.mark_removed());
+                    continue;
+                }
+                // The reader cannot tell; keep polling and count the polls that miss the card.
+                _ => {}
+            }
+        }
+        match slot.read_card(POLL_TIMEOUT) {
+            Ok(card) => {
+                set_connected(&app, &mut connected);
+                if !presence.card_seen(&card.uid) {
+                    // Still the resting card; wait before selecting it again so the reader
+                    // is not kept busy re-reading it.
+                    thread::sleep(PRESENCE_INTERVAL);
+                    continue;
+                }
+                let scanned = ScannedCard {
+                    card: card.clone(),
+                    reader_name: slot.name.clone(),
+                };
+                if let Some(ms) = card.detection_ms {
+                    println!("Card {} detected in {} ms", crate::uid_to_hex(&card.uid), ms);
+                }
+                // A card someone is scanning for is punched by that scan, not as a tap.
+                if SCANS.offer(&scanned) {
+                    continue;
+                }
+                let event = CardScannedEvent {
+                    tap_id: TAPS.issue(scanned),
+                    uid_hex: crate::uid_to_hex(&card.uid),
+                    reader_name: slot.name.clone(),
+                    reader_role: slot.role,
+                    reader_type: card.reader_type,
+                    card_family: card.family,
+                    target_type: card.target_type,
+                    detection_ms: card.detection_ms,
+                    uid: card.uid,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                if let Err(e) = app.emit(CARD_SCANNED_EVENT, event) {
+                    println!("Failed to emit {}: {}", CARD_SCANNED_EVENT, e);
+                }
+            }
+            Err(ReaderError::Timeout) => {
+                set_connected(&app, &mut connected);
+                report_removed(&app, slot, presence.poll_empty());
+            }
+            Err(e) => {
+                presence.mark_removed();
+                if connected != Some(false) {
+                    println!("Card reader '{}' unavailable: {}", slot.name, e);
+                    let _ = app.emit(READER_DISCONNECTED_EVENT, readers::health());
+                    connected = Some(false);
+                }
+                thread::sleep(RETRY_DELAY);
+            }
+        }
+    }
+}
+
+fn report_removed(app: &AppHandle, slot: &ReaderSlot, uid: Option<Vec<u8>>) {
+    if let Some(uid) = uid {
+        let event = CardRemovedEvent {
+            uid_hex: crate::uid_to_hex(&uid),
+            reader_name: slot.name.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let _ = app.emit(CARD_REMOVED_EVENT, event);
+    }
+}
+
+/// Announces a reader that answers again, with the supervisor's health of every reader.
+fn set_connected(app: &AppHandle, connected: &mut Option<bool>) {
+    if *connected == Some(true) {
+        return;
+    }
+    *connected = Some(true);
+    let _ = app.emit(READER_CONNECTED_EVENT, readers::health());
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use diesel::prelude::*;
+
+use common::{card_by_number, database};
+use urniknet_terminal_lib::badges::{link_badge, BADGE_ASSIGNED_ERROR};
+use urniknet_terminal_lib::models::{Card, NewPunch, Punch};
+use urniknet_terminal_lib::schema::{cards, punches};
+
+const OLD_BADGE: &str = "04:11:22:33";
+const NEW_BADGE: &str = "04:aa:bb:cc";
+
+fn add_card(conn: &mut SqliteConnection, number: &str, employee_id: Option<&str>) -> i32 {
+    common::add_card(conn, number, employee_id).id
+}
+
+fn add_punch(conn: &mut SqliteConnection, card_id: i32) {
+    let punch = NewPunch {
+        card_id,
+        status: "In",
+        credential_type: None,
+        reader_name: None,
+    };
+    diesel::insert_into(punches::table).values(&punch).execute(conn).unwrap();
+}
+
+fn all_cards(conn: &mut SqliteConnection) -> Vec<Card> {
+    cards::table.order(cards::id).load::<Card>(conn).unwrap()
+}
+
+#[test]
+fn enrolls_a_badge_seen_for_the_first_time() {
+    let mut conn = database();
+
+    link_badge(&mut conn, "E1042", NEW_BADGE).unwrap();
+
+    let card = card_by_number(&mut conn, NEW_BADGE).unwrap();
+    assert_eq!(card.user_id.as_deref(), Some("E1042"));
+}
+
+#[test]
+fn claims_an_unassigned_row_of_the_badge() {
+    let mut conn = database();
+    let id = add_card(&mut conn, NEW_BADGE, None);
+
+    link_badge(&mut conn, "E1042", NEW_BADGE).unwrap();
+
+    let cards = all_cards(&mut conn);
+    assert_eq!(cards.len(), 1);
+    assert_eq!((cards[0].id, cards[0].user_id.as_deref()), (id, Some("E1042")));
+}
+
+#[test]
+fn reprinted_badge_keeps_the_employee_row() {
+    let mut conn = database();
+    let id = add_card(&mut conn, OLD_BADGE, Some("E1042"));
+
+    link_badge(&mut conn, "E1042", NEW_BADGE).unwrap();
+
+    let card = card_by_number(&mut conn, NEW_BADGE).unwrap();
+    assert_eq!(card.id, id);
+    assert!(card_by_number(&mut conn, OLD_BADGE).is_none());
+}
+
+#[test]
+fn merges_a_badge_enrolled_earlier_into_the_employee_row() {
+    let mut conn = database();
+    let employee = add_card(&mut conn, OLD_BADGE, Some("E1042"));
+    let orphan = add_card(&mut conn, NEW_BADGE, None);
+    add_punch(&mut conn, employee);
+    add_punch(&mut conn, orphan);
+
+    link_badge(&mut conn, "E1042", NEW_BADGE).unwrap();
+
+    let cards = all_cards(&mut conn);
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].id, employee);
+    assert_eq!(cards[0].card_number, NEW_BADGE);
+    let punches = punches::table.load::<Punch>(&mut conn).unwrap();
+    assert_eq!(punches.len(), 2);
+    assert!(punches.iter().all(|punch| punch.card_id == employee));
+}
+
+#[test]
+fn refuses_a_badge_still_assigned_to_another_employee() {
+    let mut conn = database();
+    let previous = add_card(&mut conn, NEW_BADGE, Some("E0007"));
+    let employee = add_card(&mut conn, OLD_BADGE, Some("E1042"));
+    add_punch(&mut conn, previous);
+
+    assert_eq!(link_badge(&mut conn, "E1042", NEW_BADGE), Err(BADGE_ASSIGNED_ERROR.to_string()));
+
+    let cards = all_cards(&mut conn);
+    assert_eq!(cards.len(), 2);
+    assert_eq!((cards[0].id, cards[0].user_id.as_deref()), (previous, Some("E0007")));
+    assert_eq!((cards[1].id, cards[1].card_number.as_str()), (employee, OLD_BADGE));
+    // Without a row of its own, the new employee cannot take the badge over either.
+    assert_eq!(link_badge(&mut conn, "E2000", NEW_BADGE), Err(BADGE_ASSIGNED_ERROR.to_string()));
+}
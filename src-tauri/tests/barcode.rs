@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::barcode::{qr_uid, BarcodeReader, QrCredential, QrError, QR_PREFIX};
+use urniknet_terminal_lib::reader::{CardFamily, Reader, ReaderError, ReaderType};
+
+const KEY: &str = "000102030405060708090a0b0c0d0e0f";
+const NOW: i64 = 1_792_300_000;
+
+fn credential() -> QrCredential {
+    QrCredential::new(KEY).unwrap()
+}
+
+fn reader(lines: &[&str]) -> BarcodeReader {
+    let lines: VecDeque<String> = lines.iter().map(|line| line.to_string()).collect();
+    BarcodeReader::new(Box::new(lines), credential())
+}
+
+#[test]
+fn signed_payload_verifies() {
+    let payload = credential().sign("E1042", NOW + 3600);
+
+    assert!(payload.starts_with("UNQR1.E1042."));
+    let pass = credential().verify(&payload, NOW).unwrap();
+    assert_eq!(pass.employee_id, "E1042");
+    assert_eq!(pass.expires, NOW + 3600);
+}
+
+#[test]
+fn employee_ids_may_contain_dots() {
+    let payload = credential().sign("temp.2026.17", NOW + 60);
+
+    assert_eq!(credential().verify(&payload, NOW).unwrap().employee_id, "temp.2026.17");
+}
+
+#[test]
+fn tampered_or_foreign_payloads_are_rejected() {
+    let payload = credential().sign("E1042", NOW + 3600);
+    let other_key = QrCredential::new("ffeeddccbbaa99887766554433221100").unwrap();
+
+    assert_eq!(credential().verify(&payload.replace("E1042", "E1043"), NOW), Err(QrError::BadSignature));
+    assert_eq!(
+        credential().verify(&payload.replace(&(NOW + 3600).to_string(), &(NOW + 99999).to_string()), NOW),
+        Err(QrError::BadSignature)
+    );
+    assert_eq!(other_key.verify(&payload, NOW), Err(QrError::BadSignature));
+}
+
+#[test]
+fn expired_payload_is_rejected_after_the_signature_check() {
+    let payload = credential().sign("E1042", NOW);
+
+    assert_eq!(credential().verify(&payload, NOW), Err(QrError::Expired));
+    assert_eq!(credential().verify(&payload, NOW - 1).map(|pass| pass.expires), Ok(NOW));
+}
+
+#[test]
+fn malformed_payloads_are_rejected() {
+    for payload in ["", "UNQR1", "UNQR1.E1.123", "UNQR1..123.00", "UNQR2.E1.123.00", "UNQR1.E1.soon.00", "UNQR1.E1.123.zz"] {
+        assert_eq!(credential().verify(payload, NOW), Err(QrError::Malformed), "{:?}", payload);
+    }
+}
+
+#[test]
+fn short_keys_are_refused() {
+    assert!(matches!(QrCredential::new("0011"), Err(ReaderError::Initialization(_))));
+}
+
+#[test]
+fn valid_code_carries_the_employee_id() {
+    let reader = reader(&[]);
+    let card = reader.card_for(&credential().sign("E1042", NOW + 60), NOW);
+
+    assert_eq!(card.family, CardFamily::QrCode);
+    assert_eq!(card.reader_type, ReaderType::Barcode);
+    assert_eq!(card.credential.as_deref(), Some("E1042"));
+    assert_eq!(card.signature_valid, Some(true));
+    assert_eq!(card.uid, qr_uid("E1042"));
+    assert_eq!(card.uid.len(), 7);
+}
+
+#[test]
+fn codes_for_the_same_employee_share_a_uid() {
+    let reader = reader(&[]);
+    let first = reader.card_for(&credential().sign("E1042", NOW + 60), NOW);
+    let second = reader.card_for(&credential().sign("E1042", NOW + 86_400), NOW);
+
+    assert_eq!(first.uid, second.uid);
+    assert_ne!(first.uid, qr_uid("E1043"));
+}
+
+#[test]
+fn rejected_codes_come_without_an_employee_id() {
+    let reader = reader(&[]);
+    let expired = reader.card_for(&credential().sign("E1042", NOW - 1), NOW);
+    let forged = reader.card_for(&credential().sign("E1042", NOW + 60).replace("E1042", "E0001"), NOW);
+
+    assert_eq!((expired.credential, expired.signature_valid), (None, Some(true)));
+    assert_eq!((forged.credential, forged.signature_valid), (None, Some(false)));
+}
+
+#[test]
+fn reader_skips_other_barcodes() {
+    let payload = credential().sign("E1042", i64::MAX);
+    let mut reader = reader(&["4006381333931", &payload]);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+    assert_eq!(card.credential.as_deref(), Some("E1042"));
+    assert!(matches!(reader.read_card(Duration::from_secs(1)), Err(ReaderError::Timeout)));
+    assert_eq!(QR_PREFIX, "UNQR1");
+}
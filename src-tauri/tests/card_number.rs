@@ -0,0 +1,79 @@
+use urniknet_terminal_lib::card_number::{candidates, representations, CardNumberFormat};
+
+const UID: [u8; 4] = [0x04, 0xA1, 0xB2, 0xC3];
+/// A 26-bit Wiegand card, facility 123 and number 45678.
+const WIEGAND_26: [u8; 3] = [123, 0xB2, 0x6E];
+/// A 34-bit Wiegand card with the same facility code and number.
+const WIEGAND_34: [u8; 4] = [0, 123, 0xB2, 0x6E];
+
+#[test]
+fn formats_a_uid_every_way() {
+    assert_eq!(CardNumberFormat::Hex.format(&UID).as_deref(), Some("04:a1:b2:c3"));
+    assert_eq!(CardNumberFormat::Decimal.format(&UID).as_deref(), Some("0077705923"));
+    assert_eq!(CardNumberFormat::ReversedDecimal.format(&UID).as_deref(), Some("3283263748"));
+    assert_eq!(CardNumberFormat::Wiegand.format(&UID).as_deref(), Some("1185/45763"));
+    assert_eq!(CardNumberFormat::Wiegand.format(&[123, 0xB2, 0x6E]).as_deref(), Some("123/45678"));
+}
+
+#[test]
+fn formats_that_cannot_hold_the_uid_are_skipped() {
+    let seven_bytes = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+    assert_eq!(CardNumberFormat::Wiegand.format(&seven_bytes), None);
+    assert_eq!(CardNumberFormat::Decimal.format(&[0xFF; 9]), None);
+    assert_eq!(CardNumberFormat::Hex.format(&[]), None);
+
+    let formats: Vec<_> = representations(&seven_bytes, &CardNumberFormat::ALL)
+        .into_iter()
+        .map(|number| number.format)
+        .collect();
+    assert_eq!(formats, vec![CardNumberFormat::Hex, CardNumberFormat::Decimal, CardNumberFormat::ReversedDecimal]);
+}
+
+#[test]
+fn every_format_reads_back_what_it_wrote() {
+    for uid in [UID.to_vec(), vec![0x10, 0xE1, 0xFD, 0xE8], WIEGAND_26.to_vec(), WIEGAND_34.to_vec()] {
+        for format in CardNumberFormat::ALL {
+            let written = format.format(&uid).unwrap();
+            assert!(candidates(&written, &[format]).contains(&uid), "{:?} {}", format, written);
+        }
+    }
+}
+
+#[test]
+fn numbers_that_fit_three_bytes_are_read_at_both_widths() {
+    assert_eq!(CardNumberFormat::Wiegand.readings("123/45678"), vec![WIEGAND_26.to_vec(), WIEGAND_34.to_vec()]);
+    assert_eq!(CardNumberFormat::Decimal.readings("8106606"), vec![WIEGAND_34.to_vec(), WIEGAND_26.to_vec()]);
+    assert_eq!(
+        CardNumberFormat::ReversedDecimal.readings("7254651"),
+        vec![vec![0x7B, 0xB2, 0x6E, 0x00], vec![0x7B, 0xB2, 0x6E]]
+    );
+    assert_eq!(CardNumberFormat::Wiegand.readings("1185/45763"), vec![UID.to_vec()]);
+    assert_eq!(CardNumberFormat::Decimal.readings("77705923"), vec![UID.to_vec()]);
+}
+
+#[test]
+fn accepts_common_spellings() {
+    assert_eq!(CardNumberFormat::Hex.parse("04A1B2C3"), Some(UID.to_vec()));
+    assert_eq!(CardNumberFormat::Hex.parse(" 04 a1 b2 c3 "), Some(UID.to_vec()));
+    assert_eq!(CardNumberFormat::Decimal.parse("77705923"), Some(UID.to_vec()));
+    assert_eq!(CardNumberFormat::Wiegand.parse("123, 45678"), Some(vec![123, 0xB2, 0x6E]));
+    assert_eq!(CardNumberFormat::Wiegand.parse("123/70000"), None);
+    assert_eq!(CardNumberFormat::Decimal.parse("04:a1"), None);
+}
+
+#[test]
+fn ambiguous_numbers_yield_every_reading_in_order() {
+    let uids = candidates("12345678", &CardNumberFormat::ALL);
+    assert_eq!(
+        uids,
+        vec![
+            vec![0x12, 0x34, 0x56, 0x78],
+            vec![0x00, 0xBC, 0x61, 0x4E],
+            vec![0xBC, 0x61, 0x4E],
+            vec![0x4E, 0x61, 0xBC, 0x00],
+            vec![0x4E, 0x61, 0xBC],
+        ]
+    );
+    assert_eq!(candidates("04:a1:b2:c3", &CardNumberFormat::ALL), vec![UID.to_vec()]);
+    assert!(candidates("not a card", &CardNumberFormat::ALL).is_empty());
+}
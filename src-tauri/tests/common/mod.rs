@@ -0,0 +1,30 @@
+//! Fixtures shared by the integration tests that need a database.
+#![allow(dead_code)]
+
+use diesel::prelude::*;
+
+use urniknet_terminal_lib::models::{Card, NewCard};
+use urniknet_terminal_lib::run_pending_migrations;
+use urniknet_terminal_lib::schema::cards;
+
+/// An empty in-memory database with every migration applied.
+pub fn database() -> SqliteConnection {
+    let mut conn = SqliteConnection::establish(":memory:").unwrap();
+    run_pending_migrations(&mut conn);
+    conn
+}
+
+/// Adds a card row and returns it.
+pub fn add_card(conn: &mut SqliteConnection, number: &str, employee_id: Option<&str>) -> Card {
+    let card = NewCard {
+        card_number: number,
+        card_name: None,
+        user_id: employee_id,
+    };
+    diesel::insert_into(cards::table).values(&card).execute(conn).unwrap();
+    card_by_number(conn, number).unwrap()
+}
+
+pub fn card_by_number(conn: &mut SqliteConnection, number: &str) -> Option<Card> {
+    cards::table.filter(cards::card_number.eq(number)).first::<Card>(conn).optional().unwrap()
+}
@@ -0,0 +1,141 @@
+use urniknet_terminal_lib::config::{
+    read_config, OriginalityPolicy, ReaderConfig, ReaderRole, TerminalConfig, DEFAULT_READER_NAME,
+};
+use urniknet_terminal_lib::reader::{CardFamily, DetectedCard, ReaderType};
+
+#[test]
+fn single_reader_configuration_toggles() {
+    let config: TerminalConfig = serde_json::from_str(r#"{"reader": {"type": "rdm6300", "path": "/dev/ttyAMA0"}}"#).unwrap();
+
+    let readers = config.reader_configs();
+    assert_eq!(readers.len(), 1);
+    assert_eq!(readers[0].name, DEFAULT_READER_NAME);
+    assert_eq!(readers[0].role, ReaderRole::Toggle);
+    assert_eq!(readers[0].reader.path().as_deref(), Some("/dev/ttyAMA0"));
+}
+
+#[test]
+fn named_readers_keep_their_roles_and_devices() {
+    let config: TerminalConfig = serde_json::from_str(
+        r#"{"readers": [
+            {"name": "entry", "role": "entry", "type": "pn532", "transport": "i2c", "path": "/dev/i2c-1"},
+            {"name": "exit", "role": "exit", "type": "pn532", "transport": "uart", "path": "/dev/ttyUSB0"},
+            {"name": "desk", "role": "leave_only", "type": "rdm6300"}
+        ]}"#,
+    )
+    .unwrap();
+
+    let readers = config.reader_configs();
+    let summary: Vec<_> = readers
+        .iter()
+        .map(|named| (named.name.as_str(), named.role, named.reader.transport_name(), named.reader.path()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("entry", ReaderRole::Entry, "i2c", Some(String::from("/dev/i2c-1"))),
+            ("exit", ReaderRole::Exit, "uart", Some(String::from("/dev/ttyUSB0"))),
+            ("desk", ReaderRole::LeaveOnly, "uart", None),
+        ]
+    );
+    assert!(matches!(readers[2].reader, ReaderConfig::Rdm6300 { .. }));
+}
+
+#[test]
+fn reader_role_defaults_to_toggle() {
+    let config: TerminalConfig =
+        serde_json::from_str(r#"{"readers": [{"name": "lobby", "type": "pn532"}]}"#).unwrap();
+
+    assert_eq!(config.reader_configs()[0].role, ReaderRole::Toggle);
+}
+
+#[test]
+fn card_numbers_show_the_display_format_first() {
+    let config: TerminalConfig =
+        serde_json::from_str(r#"{"card_numbers": {"display": "wiegand", "accept": ["decimal"]}}"#).unwrap();
+
+    let numbers: Vec<_> = config
+        .card_numbers
+        .representations(&[123, 0xB2, 0x6E])
+        .into_iter()
+        .map(|number| number.number)
+        .collect();
+    assert_eq!(numbers, vec!["123/45678", "0008106606", "7b:b2:6e"]);
+}
+
+#[test]
+fn card_numbers_always_accept_hex() {
+    let config: TerminalConfig = serde_json::from_str(r#"{"card_numbers": {"accept": ["decimal"]}}"#).unwrap();
+
+    assert_eq!(config.card_numbers.candidates("12"), vec![vec![0, 0, 0, 12], vec![0, 0, 12], vec![0x12]]);
+    assert_eq!(config.card_numbers.candidates("7b:b2:6e"), vec![vec![0x7B, 0xB2, 0x6E]]);
+    let wiegand = TerminalConfig::default().card_numbers.candidates("123/45678");
+    assert!(wiegand.contains(&vec![123, 0xB2, 0x6E]));
+    assert!(wiegand.contains(&vec![0, 123, 0xB2, 0x6E]));
+}
+
+#[test]
+fn barcode_scanner_needs_the_qr_key() {
+    let config: TerminalConfig =
+        serde_json::from_str(r#"{"reader": {"type": "barcode", "path": "/dev/ttyACM0"}}"#).unwrap();
+    assert_eq!(config.reader.transport_name(), "uart");
+    assert!(config.reader.open(&config).is_err());
+
+    let config: TerminalConfig = serde_json::from_str(
+        r#"{"reader": {"type": "barcode", "path": "/dev/input/event3", "interface": "hid"},
+            "qr": {"key": "000102030405060708090a0b0c0d0e0f"}}"#,
+    )
+    .unwrap();
+    assert_eq!(config.reader.transport_name(), "evdev");
+    assert!(config.reader.open(&config).is_ok());
+}
+
+fn card(family: CardFamily, reader_type: ReaderType, signature_valid: Option<bool>, credential: Option<&str>) -> DetectedCard {
+    DetectedCard {
+        signature_valid,
+        credential: credential.map(String::from),
+        ..DetectedCard::from_uid(vec![0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66], family, reader_type)
+    }
+}
+
+#[test]
+fn require_admits_only_cards_that_prove_they_are_genuine() {
+    let genuine = card(CardFamily::MifareUltralight, ReaderType::NFC, Some(true), None);
+    let require = OriginalityPolicy::Require;
+
+    assert!(require.admits(&genuine));
+    assert!(!require.admits(&card(CardFamily::MifareUltralight, ReaderType::NFC, Some(false), None)));
+    assert!(!require.admits(&card(CardFamily::MifareUltralight, ReaderType::NFC, None, None)));
+    assert!(!require.admits(&card(CardFamily::MifareClassic1k, ReaderType::NFC, None, Some("E1"))));
+    assert!(!require.admits(&card(CardFamily::Em4100, ReaderType::RFID, None, None)));
+    assert!(!require.admits(&card(CardFamily::Unknown, ReaderType::Wiegand, None, None)));
+    assert!(require.admits(&card(CardFamily::IsoDep, ReaderType::NFC, None, Some("E1"))));
+    assert!(!require.admits(&card(CardFamily::IsoDep, ReaderType::NFC, None, None)));
+    assert!(require.admits(&card(CardFamily::QrCode, ReaderType::Barcode, Some(true), Some("E1"))));
+    assert!(!require.admits(&card(CardFamily::QrCode, ReaderType::Barcode, Some(true), None)));
+}
+
+#[test]
+fn other_policies_admit_every_card() {
+    let forged = card(CardFamily::MifareUltralight, ReaderType::NFC, Some(false), None);
+
+    assert!(OriginalityPolicy::Warn.admits(&forged));
+    assert!(OriginalityPolicy::Off.admits(&forged));
+}
+
+#[test]
+fn only_a_missing_file_falls_back_to_the_defaults() {
+    let dir = std::env::temp_dir().join(format!("terminal-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("terminal.json");
+
+    assert!(read_config(&path).unwrap().readers.is_empty());
+
+    std::fs::write(&path, r#"{"originality": "require"}"#).unwrap();
+    assert_eq!(read_config(&path).unwrap().originality, OriginalityPolicy::Require);
+
+    std::fs::write(&path, r#"{"originality": "require",}"#).unwrap();
+    let error = read_config(&path).err();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(error.is_some_and(|e| e.starts_with("Invalid")));
+}
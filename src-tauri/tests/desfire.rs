@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use cmac::{Cmac, Mac};
+use urniknet_terminal_lib::reader::apdu::ApduChannel;
+use urniknet_terminal_lib::reader::desfire::{
+    aes_cbc_decrypt, aes_cbc_encrypt, read_credential, DesfireCredential,
+};
+use urniknet_terminal_lib::reader::ReaderError;
+
+const KEY: [u8; 16] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF,
+];
+const KEY_HEX: &str = "00112233445566778899AABBCCDDEEFF";
+/// AID F54210, as sent on the wire (least significant byte first).
+const AID: [u8; 3] = [0x10, 0x42, 0xF5];
+const RND_B: [u8; 16] = [
+    0xB0, 0xB1, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xBB, 0xBC, 0xBD, 0xBE, 0xBF,
+];
+const TI: [u8; 4] = [0x9D, 0x00, 0xC4, 0xDF];
+
+/// AES key 0 and data files of an application.
+type Application = ([u8; 16], HashMap<u8, Vec<u8>>);
+
+struct Session {
+    enc_key: [u8; 16],
+    mac_key: [u8; 16],
+    counter: u16,
+}
+
+/// Software DESFire EV2 card with AES keys and data files in full communication mode.
+#[derive(Default)]
+struct DesfireEmulator {
+    applications: HashMap<[u8; 3], Application>,
+    selected: Option<[u8; 3]>,
+    pending_challenge: bool,
+    session: Option<Session>,
+    corrupt_mac: bool,
+    commands: Vec<u8>,
+}
+
+impl DesfireEmulator {
+    fn with_employee_file(data: &[u8]) -> Self {
+        let mut files = HashMap::new();
+        files.insert(0x01, data.to_vec());
+        let mut emulator = Self::default();
+        emulator.applications.insert(AID, (KEY, files));
+        emulator
+    }
+
+    fn key(&self) -> [u8; 16] {
+        self.selected.map(|aid| self.applications[&aid].0).unwrap_or([0; 16])
+    }
+
+    fn handle(&mut self, command: u8, data: &[u8]) -> (Vec<u8>, u8) {
+        match command {
+            0x5A => match data.try_into() {
+                Ok(aid) if self.applications.contains_key(&aid) => {
+                    self.selected = Some(aid);
+                    self.session = None;
+                    (vec![], 0x00)
+                }
+                _ => (vec![], 0xA0),
+            },
+            0x71 if self.selected.is_some() => {
+                self.pending_challenge = true;
+                (aes_cbc_encrypt(&self.key(), &[0; 16], &RND_B), 0xAF)
+            }
+            0xAF if self.pending_challenge => {
+                self.pending_challenge = false;
+                let token = aes_cbc_decrypt(&self.key(), &[0; 16], data);
+                let mut rnd_b_rotated = RND_B;
+                rnd_b_rotated.rotate_left(1);
+                if token.len() != 32 || token[16..] != rnd_b_rotated {
+                    return (vec![], 0xAE);
+                }
+                let rnd_a: [u8; 16] = token[..16].try_into().unwrap();
+                let mut answer = TI.to_vec();
+                answer.extend_from_slice(&rnd_a[1..]);
+                answer.push(rnd_a[0]);
+                answer.extend_from_slice(&[0; 12]);
+                let key = self.key();
+                self.session = Some(Session {
+                    enc_key: cmac(&key, &session_vector([0xA5, 0x5A], &rnd_a)),
+                    mac_key: cmac(&key, &session_vector([0x5A, 0xA5], &rnd_a)),
+                    counter: 0,
+                });
+                (aes_cbc_encrypt(&key, &[0; 16], &answer), 0x00)
+            }
+            0xAD => self.read_data(data),
+            _ => (vec![], 0x1C),
+        }
+    }
+
+    fn read_data(&mut self, data: &[u8]) -> (Vec<u8>, u8) {
+        let selected = self.selected;
+        let corrupt_mac = self.corrupt_mac;
+        let Some(session) = self.session.as_mut() else {
+            return (vec![], 0xAE);
+        };
+        let (header, mac) = data.split_at(7);
+        let mut mac_input = vec![0xAD];
+        mac_input.extend_from_slice(&session.counter.to_le_bytes());
+        mac_input.extend_from_slice(&TI);
+        mac_input.extend_from_slice(header);
+        if truncate(&cmac(&session.mac_key, &mac_input)) != mac {
+            return (vec![], 0x1E);
+        }
+        let offset = u32::from_le_bytes([header[1], header[2], header[3], 0]) as usize;
+        let length = u32::from_le_bytes([header[4], header[5], header[6], 0]) as usize;
+        let file = match self.applications[&selected.unwrap()].1.get(&header[0]) {
+            Some(file) if offset + length <= file.len() => file[offset..offset + length].to_vec(),
+            Some(_) => return (vec![], 0xBE),
+            None => return (vec![], 0xF0),
+        };
+
+        session.counter += 1;
+        let mut plaintext = file;
+        plaintext.push(0x80);
+        while plaintext.len() % 16 != 0 {
+            plaintext.push(0x00);
+        }
+        let mut iv_input = [0u8; 16];
+        iv_input[..2].copy_from_slice(&[0x5A, 0xA5]);
+        iv_input[2..6].copy_from_slice(&TI);
+        iv_input[6..8].copy_from_slice(&session.counter.to_le_bytes());
+        let mut iv = GenericArray::clone_from_slice(&iv_input);
+        Aes128::new(GenericArray::from_slice(&session.enc_key)).encrypt_block(&mut iv);
+        let ciphertext = aes_cbc_encrypt(&session.enc_key, &iv.into(), &plaintext);
+
+        let mut mac_input = vec![0x00];
+        mac_input.extend_from_slice(&session.counter.to_le_bytes());
+        mac_input.extend_from_slice(&TI);
+        mac_input.extend_from_slice(&ciphertext);
+        let mut mac = truncate(&cmac(&session.mac_key, &mac_input));
+        if corrupt_mac {
+            mac[0] ^= 0xFF;
+        }
+        let mut answer = ciphertext;
+        answer.extend_from_slice(&mac);
+        (answer, 0x00)
+    }
+}
+
+impl ApduChannel for DesfireEmulator {
+    fn transceive(&mut self, apdu: &[u8]) -> Result<Vec<u8>, ReaderError> {
+        assert_eq!(apdu[0], 0x90, "not a wrapped native command");
+        assert_eq!(*apdu.last().unwrap(), 0x00, "missing Le");
+        self.commands.push(apdu[1]);
+        let data = if apdu.len() > 5 { &apdu[5..apdu.len() - 1] } else { &[][..] };
+        let (mut response, status) = self.handle(apdu[1], data);
+        response.extend_from_slice(&[0x91, status]);
+        Ok(response)
+    }
+}
+
+fn session_vector(label: [u8; 2], rnd_a: &[u8; 16]) -> Vec<u8> {
+    let mut sv = vec![label[0], label[1], 0x00, 0x01, 0x00, 0x80, rnd_a[0], rnd_a[1]];
+    sv.extend((0..6).map(|i| rnd_a[2 + i] ^ RND_B[i]));
+    sv.extend_from_slice(&RND_B[6..]);
+    sv.extend_from_slice(&rnd_a[8..]);
+    sv
+}
+
+fn cmac(key: &[u8; 16], data: &[u8]) -> [u8; 16] {
+    let mut mac = <Cmac<Aes128> as Mac>::new(GenericArray::from_slice(key));
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn truncate(mac: &[u8; 16]) -> Vec<u8> {
+    mac.iter().skip(1).step_by(2).copied().collect()
+}
+
+fn credential(key_hex: &str) -> DesfireCredential {
+    DesfireCredential::new("F54210", 0, key_hex, 0x01, 0, 16).unwrap()
+}
+
+fn employee_file() -> Vec<u8> {
+    let mut data = b"004217".to_vec();
+    data.resize(16, 0x00);
+    data
+}
+
+#[test]
+fn reads_employee_id_after_ev2_authentication() {
+    let mut card = DesfireEmulator::with_employee_file(&employee_file());
+
+    let employee_id = read_credential(&mut card, &credential(KEY_HEX)).unwrap();
+
+    assert_eq!(employee_id.as_deref(), Some("004217"));
+    assert_eq!(card.commands, vec![0x5A, 0x71, 0xAF, 0xAD]);
+}
+
+#[test]
+fn wrong_key_fails_authentication() {
+    let mut card = DesfireEmulator::with_employee_file(&employee_file());
+
+    match read_credential(&mut card, &credential("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF")) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("authentication error"), "{}", msg),
+        other => panic!("expected an authentication error, got {:?}", other),
+    }
+    assert!(!card.commands.contains(&0xAD), "file read without authentication");
+}
+
+#[test]
+fn missing_application_is_reported() {
+    let mut card = DesfireEmulator::with_employee_file(&employee_file());
+    let other = DesfireCredential::new("000001", 0, KEY_HEX, 0x01, 0, 16).unwrap();
+
+    match read_credential(&mut card, &other) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("application not found"), "{}", msg),
+        other => panic!("expected a missing application, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_tampered_response_mac() {
+    let mut card = DesfireEmulator::with_employee_file(&employee_file());
+    card.corrupt_mac = true;
+
+    match read_credential(&mut card, &credential(KEY_HEX)) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("MAC mismatch"), "{}", msg),
+        other => panic!("expected a MAC mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_invalid_configuration() {
+    assert!(DesfireCredential::new("F542", 0, KEY_HEX, 1, 0, 16).is_err());
+    assert!(DesfireCredential::new("F54210", 0, "0011", 1, 0, 16).is_err());
+    assert!(DesfireCredential::new("F54210", 0, KEY_HEX, 1, 0, 0).is_err());
+}
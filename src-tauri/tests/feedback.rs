@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use urniknet_terminal_lib::feedback::mock::MockGpio;
+use urniknet_terminal_lib::feedback::{default_pattern, GpioBackend, Outcome, Player, Pn532Gpio, Pulse};
+use urniknet_terminal_lib::reader::ReaderError;
+
+fn lines(names: &[(&str, u32)]) -> HashMap<String, u32> {
+    names.iter().map(|(name, line)| (name.to_string(), *line)).collect()
+}
+
+fn pulse(outputs: &[&str]) -> Pulse {
+    Pulse {
+        outputs: outputs.iter().map(|output| output.to_string()).collect(),
+        on_ms: 0,
+        off_ms: 0,
+    }
+}
+
+#[test]
+fn plays_configured_pattern() {
+    let gpio = MockGpio::new();
+    let mut patterns = HashMap::new();
+    patterns.insert(Outcome::UnknownCard, vec![pulse(&["red", "buzzer"]), pulse(&["red"])]);
+    let mut player = Player::new(
+        Box::new(gpio.clone()),
+        lines(&[("buzzer", 17), ("green", 27), ("red", 22)]),
+        patterns,
+    );
+
+    player.play(Outcome::UnknownCard).unwrap();
+
+    assert_eq!(
+        gpio.changes(),
+        vec![
+            (vec![22, 17], true),
+            (vec![22, 17], false),
+            (vec![22], true),
+            (vec![22], false),
+        ]
+    );
+}
+
+#[test]
+fn falls_back_to_default_pattern() {
+    let player = Player::new(Box::new(MockGpio::new()), HashMap::new(), HashMap::new());
+
+    for outcome in [
+        Outcome::PunchedIn,
+        Outcome::PunchedOut,
+        Outcome::LeaveRegistered,
+        Outcome::AlreadyRecorded,
+        Outcome::UnknownCard,
+        Outcome::Error,
+    ] {
+        assert_eq!(player.pattern(outcome), default_pattern(outcome));
+        assert!(!player.pattern(outcome).is_empty());
+    }
+}
+
+#[test]
+fn skips_outputs_without_a_line() {
+    let gpio = MockGpio::new();
+    let mut patterns = HashMap::new();
+    patterns.insert(Outcome::PunchedIn, vec![pulse(&["green", "buzzer"]), pulse(&["green"])]);
+    let mut player = Player::new(Box::new(gpio.clone()), lines(&[("buzzer", 4)]), patterns);
+
+    player.play(Outcome::PunchedIn).unwrap();
+
+    assert_eq!(gpio.changes(), vec![(vec![4], true), (vec![4], false)]);
+}
+
+#[test]
+fn parses_patterns_from_configuration() {
+    let patterns: HashMap<Outcome, Vec<Pulse>> = serde_json::from_str(
+        r#"{"punched_out": [{"outputs": ["green"], "on_ms": 200}, {"outputs": ["buzzer"], "on_ms": 50, "off_ms": 50}]}"#,
+    )
+    .unwrap();
+
+    let pattern = &patterns[&Outcome::PunchedOut];
+    assert_eq!(pattern.len(), 2);
+    assert_eq!((pattern[0].on_ms, pattern[0].off_ms), (200, 0));
+    assert_eq!(pattern[1].outputs, vec!["buzzer"]);
+}
+
+#[test]
+fn pn532_backend_keeps_other_pins() {
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let sink = written.clone();
+    let mut gpio = Pn532Gpio::new(
+        &[1, 4],
+        false,
+        Box::new(move |levels| {
+            sink.lock().unwrap().push(levels);
+            Ok(())
+        }),
+    )
+    .unwrap();
+
+    gpio.set(&[1], true).unwrap();
+    gpio.set(&[4], true).unwrap();
+    gpio.set(&[1], false).unwrap();
+
+    assert_eq!(*written.lock().unwrap(), vec![0b00_0010, 0b01_0010, 0b01_0000]);
+}
+
+#[test]
+fn pn532_backend_inverts_active_low_lines() {
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let sink = written.clone();
+    let mut gpio = Pn532Gpio::new(
+        &[0],
+        true,
+        Box::new(move |levels| {
+            sink.lock().unwrap().push(levels);
+            Ok(())
+        }),
+    )
+    .unwrap();
+
+    gpio.set(&[0], true).unwrap();
+
+    assert_eq!(*written.lock().unwrap(), vec![0b11_1110]);
+}
+
+#[test]
+fn pn532_backend_rejects_missing_pins() {
+    let result = Pn532Gpio::new(&[6], false, Box::new(|_| Ok(())));
+
+    assert!(matches!(result, Err(ReaderError::Initialization(_))));
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::apdu::ApduChannel;
+use urniknet_terminal_lib::reader::hce::{read_token, request_token, sign_token, PhoneCredential};
+use urniknet_terminal_lib::reader::pn532::mock::MockTransport;
+use urniknet_terminal_lib::reader::pn532::Pn532Reader;
+use urniknet_terminal_lib::reader::{CardFamily, Reader, ReaderError, UidKind};
+
+const AID_HEX: &str = "F0554E494B4E4554";
+const KEY_HEX: &str = "000102030405060708090A0B0C0D0E0F";
+
+/// Stand-in for the phone app: answers SELECT of its AID and signs challenges.
+struct HceResponder {
+    aid: Vec<u8>,
+    key: Vec<u8>,
+    token: &'static str,
+    selected: bool,
+    apdus: Vec<Vec<u8>>,
+}
+
+impl HceResponder {
+    fn new(token: &'static str) -> Self {
+        let credential = credential();
+        Self {
+            key: credential.employee_key(token),
+            aid: credential.aid,
+            token,
+            selected: false,
+            apdus: Vec::new(),
+        }
+    }
+}
+
+impl ApduChannel for HceResponder {
+    fn transceive(&mut self, apdu: &[u8]) -> Result<Vec<u8>, ReaderError> {
+        self.apdus.push(apdu.to_vec());
+        let data = &apdu[5..5 + apdu[4] as usize];
+        let mut response = match (apdu[0], apdu[1]) {
+            (0x00, 0xA4) if data == self.aid.as_slice() => {
+                self.selected = true;
+                vec![]
+            }
+            (0x00, 0xA4) => return Ok(vec![0x6A, 0x82]),
+            (0x80, 0x10) if self.selected => {
+                let mut answer = self.token.as_bytes().to_vec();
+                answer.extend_from_slice(&sign_token(&self.key, data, self.token.as_bytes()));
+                answer
+            }
+            _ => return Ok(vec![0x69, 0x85]),
+        };
+        response.extend_from_slice(&[0x90, 0x00]);
+        Ok(response)
+    }
+}
+
+fn credential() -> PhoneCredential {
+    PhoneCredential::new(AID_HEX, KEY_HEX).unwrap()
+}
+
+#[test]
+fn phone_returns_signed_token() {
+    let mut phone = HceResponder::new("004217");
+
+    let token = read_token(&mut phone, &credential()).unwrap();
+
+    assert_eq!(token.as_deref(), Some("004217"));
+    assert_eq!(&phone.apdus[0][..5], &[0x00, 0xA4, 0x04, 0x00, 0x08]);
+    assert_eq!(&phone.apdus[1][..5], &[0x80, 0x10, 0x00, 0x00, 0x10]);
+}
+
+#[test]
+fn rejects_token_signed_with_other_key() {
+    let mut phone = HceResponder::new("004217");
+    phone.key = vec![0xEE; 16];
+
+    match read_token(&mut phone, &credential()) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("does not verify"), "{}", msg),
+        other => panic!("expected a signature failure, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_token_for_another_employee() {
+    // The key provisioned to employee 004217's phone, claiming to be 001000.
+    let mut phone = HceResponder::new("001000");
+    phone.key = credential().employee_key("004217");
+
+    match read_token(&mut phone, &credential()) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("does not verify"), "{}", msg),
+        other => panic!("expected a signature failure, got {:?}", other),
+    }
+}
+
+#[test]
+fn master_key_itself_signs_for_no_one() {
+    let mut phone = HceResponder::new("004217");
+    phone.key = credential().key;
+
+    assert!(read_token(&mut phone, &credential()).is_err());
+}
+
+#[test]
+fn rejects_replayed_answer() {
+    let mut phone = HceResponder::new("004217");
+    let mut recorded = "004217".as_bytes().to_vec();
+    recorded.extend_from_slice(&sign_token(&phone.key, &[0x11; 16], b"004217"));
+    recorded.extend_from_slice(&[0x90, 0x00]);
+
+    struct Replay(Vec<u8>);
+    impl ApduChannel for Replay {
+        fn transceive(&mut self, _apdu: &[u8]) -> Result<Vec<u8>, ReaderError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    assert!(request_token(&mut Replay(recorded), &credential(), &[0x22; 16]).is_err());
+    assert!(request_token(&mut phone, &credential(), &[0x22; 16]).is_err(), "token served before SELECT");
+}
+
+#[test]
+fn phone_without_terminal_app_is_reported() {
+    let mut phone = HceResponder::new("004217");
+    phone.aid = vec![0xA0, 0x00, 0x00, 0x00, 0x03];
+
+    match read_token(&mut phone, &credential()) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("6a82"), "{}", msg),
+        other => panic!("expected a refused SELECT, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_invalid_phone_configuration() {
+    assert!(PhoneCredential::new("F055", KEY_HEX).is_err());
+    assert!(PhoneCredential::new(AID_HEX, "0011").is_err());
+}
+
+#[test]
+fn pn532_selects_terminal_app_on_iso_dep_targets() {
+    let mock = MockTransport::new();
+    mock.push_response(0x14, &[]);
+    // A phone: random UID, SAK 0x20 and a short ATS.
+    mock.push_response(0x4A, &[0x01, 0x01, 0x00, 0x04, 0x20, 0x04, 0x08, 0x12, 0x34, 0x56, 0x05, 0x78, 0x80, 0x70, 0x02]);
+    mock.push_response(0x40, &[0x00, 0x6A, 0x82]);
+    let mut reader = Pn532Reader::new(Box::new(mock.clone())).with_phone_credential(credential());
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.family, CardFamily::IsoDep);
+    assert_eq!(card.uid_kind, UidKind::Random);
+    assert_eq!(card.credential, None);
+    assert_eq!(&mock.written()[2][5..13], &[0xD4, 0x40, 0x01, 0x00, 0xA4, 0x04, 0x00, 0x08]);
+}
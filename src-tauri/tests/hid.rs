@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::hid::{HidWedgeReader, InputEvent, KeyDecoder, WedgeFormat};
+use urniknet_terminal_lib::reader::{Reader, ReaderError, UidKind};
+
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTSHIFT: u16 = 42;
+
+/// Key code of a digit on the main row.
+fn digit_code(digit: char) -> u16 {
+    match digit {
+        '0' => 11,
+        d => d.to_digit(10).unwrap() as u16 + 1,
+    }
+}
+
+/// Events of a reader typing `digits` and Enter, 4 ms per key, starting at `start_us`.
+fn typed(digits: &str, start_us: i64) -> Vec<InputEvent> {
+    let mut events = Vec::new();
+    let mut time = start_us;
+    for code in digits.chars().map(digit_code).chain([KEY_ENTER]) {
+        events.push(InputEvent::key(time, code, 1));
+        events.push(InputEvent::key(time + 2_000, code, 0));
+        time += 4_000;
+    }
+    events
+}
+
+fn decode(decoder: &mut KeyDecoder, events: &[InputEvent]) -> Vec<String> {
+    events.iter().filter_map(|event| decoder.push(event)).collect()
+}
+
+#[test]
+fn decodes_typed_number() {
+    let mut decoder = KeyDecoder::new();
+
+    assert_eq!(decode(&mut decoder, &typed("0012345678", 0)), vec!["0012345678"]);
+}
+
+#[test]
+fn shift_types_upper_case() {
+    let mut decoder = KeyDecoder::new();
+    let events = [
+        InputEvent::key(0, KEY_LEFTSHIFT, 1),
+        InputEvent::key(1_000, 30, 1),
+        InputEvent::key(2_000, KEY_LEFTSHIFT, 0),
+        InputEvent::key(3_000, 48, 1),
+        InputEvent::key(4_000, 2, 1),
+        InputEvent::key(5_000, KEY_ENTER, 1),
+    ];
+
+    assert_eq!(decode(&mut decoder, &events), vec!["Ab1"]);
+}
+
+#[test]
+fn stale_keys_start_a_new_line() {
+    let mut decoder = KeyDecoder::new();
+    let mut events = vec![InputEvent::key(0, digit_code('9'), 1)];
+    events.extend(typed("42", 2_000_000));
+
+    assert_eq!(decode(&mut decoder, &events), vec!["42"]);
+}
+
+#[test]
+fn ignores_empty_lines_and_other_events() {
+    let mut decoder = KeyDecoder::new();
+    let sync = InputEvent {
+        time_us: 0,
+        kind: 0,
+        code: 0,
+        value: 0,
+    };
+    let events = [sync, InputEvent::key(0, KEY_ENTER, 1), InputEvent::key(0, KEY_ENTER, 0)];
+
+    assert!(decode(&mut decoder, &events).is_empty());
+}
+
+#[test]
+fn event_layout_round_trips() {
+    let event = InputEvent::key(1_700_000_000_123_456, 30, 1);
+
+    assert_eq!(InputEvent::parse(&event.to_bytes()), event);
+}
+
+#[test]
+fn converts_typed_numbers_to_uids() {
+    assert_eq!(WedgeFormat::Decimal.parse("0012345678"), Some(vec![0x00, 0xBC, 0x61, 0x4E]));
+    assert_eq!(WedgeFormat::Decimal.parse("1099511627775"), Some(vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF]));
+    assert_eq!(WedgeFormat::Hex.parse("DEADBEEF"), Some(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+    assert_eq!(WedgeFormat::Hex.parse("ABC"), Some(vec![0x0A, 0xBC]));
+    assert_eq!(WedgeFormat::Decimal.parse("12AB"), None);
+}
+
+#[test]
+fn replays_recorded_event_stream() {
+    let path = std::env::temp_dir().join(format!("hid-wedge-{}.events", std::process::id()));
+    let mut events = typed("0012345678", 0);
+    events.extend(typed("4242", 5_000_000));
+    let stream: Vec<u8> = events.iter().flat_map(|event| event.to_bytes()).collect();
+    std::fs::write(&path, stream).unwrap();
+
+    let mut reader = HidWedgeReader::new(path.to_string_lossy(), WedgeFormat::Decimal).without_grab();
+    let first = reader.read_uid(Duration::from_secs(1)).unwrap();
+    let second = reader.read_uid(Duration::from_secs(1)).unwrap();
+    let third = reader.read_uid(Duration::from_millis(200));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(first, vec![0x00, 0xBC, 0x61, 0x4E]);
+    assert_eq!(second, vec![0x00, 0x00, 0x10, 0x92]);
+    assert!(matches!(third, Err(ReaderError::Timeout)));
+}
+
+#[test]
+fn numbers_starting_with_0x08_are_not_random_uids() {
+    let path = std::env::temp_dir().join(format!("hid-wedge-{}-0x08.events", std::process::id()));
+    let stream: Vec<u8> = typed("134217728", 0).iter().flat_map(|event| event.to_bytes()).collect();
+    std::fs::write(&path, stream).unwrap();
+
+    let mut reader = HidWedgeReader::new(path.to_string_lossy(), WedgeFormat::Decimal).without_grab();
+    let card = reader.read_card(Duration::from_secs(1));
+    std::fs::remove_file(&path).unwrap();
+
+    let card = card.unwrap();
+    assert_eq!(card.uid, vec![0x08, 0x00, 0x00, 0x00]);
+    assert_eq!(card.uid_kind, UidKind::Unique);
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::ndef::{credential_from_message, find_message, parse_message, scan_tlvs, TlvScan};
+use urniknet_terminal_lib::reader::pn532::mock::MockTransport;
+use urniknet_terminal_lib::reader::pn532::Pn532Reader;
+use urniknet_terminal_lib::reader::{CardFamily, Reader};
+
+const NTAG_UID: [u8; 7] = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+
+/// Short well-known text record "en" / `text`, as the only record of its message.
+fn text_message(text: &str) -> Vec<u8> {
+    let mut message = vec![0xD1, 0x01, (text.len() + 3) as u8, b'T', 0x02, b'e', b'n'];
+    message.extend_from_slice(text.as_bytes());
+    message
+}
+
+/// A Type 2 tag data area holding `message` in an NDEF TLV, padded to `len` bytes.
+fn data_area(message: &[u8], len: usize) -> Vec<u8> {
+    let mut data = vec![0x01, 0x03, 0xA0, 0x0C, 0x34, 0x03, message.len() as u8];
+    data.extend_from_slice(message);
+    data.push(0xFE);
+    data.resize(len, 0x00);
+    data
+}
+
+fn ntag_reader(mock: &MockTransport) -> Pn532Reader {
+    mock.push_response(0x14, &[]);
+    let mut target = vec![0x01, 0x01, 0x00, 0x44, 0x00, NTAG_UID.len() as u8];
+    target.extend_from_slice(&NTAG_UID);
+    mock.push_response(0x4A, &target);
+    Pn532Reader::new(Box::new(mock.clone())).with_ndef_credential()
+}
+
+fn push_pages(mock: &MockTransport, pages: &[u8]) {
+    let mut response = vec![0x00];
+    response.extend_from_slice(pages);
+    mock.push_response(0x40, &response);
+}
+
+#[test]
+fn parses_text_record() {
+    let records = parse_message(&text_message("E-1042")).unwrap();
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].text().as_deref(), Some("E-1042"));
+    assert_eq!(credential_from_message(&text_message(" E-1042 ")).unwrap().as_deref(), Some("E-1042"));
+}
+
+#[test]
+fn parses_uri_record() {
+    let message = [0xD1, 0x01, 0x0B, b'U', 0x04, b'u', b'r', b'n', b'i', b'k', b'.', b's', b'i', b'/', b'7'];
+
+    assert_eq!(credential_from_message(&message).unwrap().as_deref(), Some("https://urnik.si/7"));
+}
+
+#[test]
+fn finds_message_after_other_tlvs() {
+    let message = text_message("42");
+    let mut data = vec![0x00, 0x01, 0x03, 0xA0, 0x0C, 0x34, 0x03, message.len() as u8];
+    data.extend_from_slice(&message);
+    data.push(0xFE);
+
+    assert_eq!(find_message(&data).unwrap(), Some(message));
+    assert_eq!(find_message(&[0x03, 0x00, 0xFE]).unwrap(), None);
+    assert_eq!(find_message(&[0xFE]).unwrap(), None);
+    assert!(find_message(&[0x03, 0x10, 0xD1]).is_err());
+}
+
+#[test]
+fn tells_missing_message_from_unread_data() {
+    assert_eq!(scan_tlvs(&[0x01, 0x03, 0xA0, 0x0C, 0x34, 0x00, 0x00]).unwrap(), TlvScan::NeedMore);
+    assert_eq!(scan_tlvs(&[0x00, 0x00, 0xFE]).unwrap(), TlvScan::NoMessage);
+    assert_eq!(scan_tlvs(&[0x03, 0x00]).unwrap(), TlvScan::NoMessage);
+    assert!(scan_tlvs(&[0x03, 0x10, 0xD1]).is_err());
+    assert_eq!(find_message(&[0x01, 0x03, 0xA0, 0x0C, 0x34]).unwrap(), None);
+}
+
+#[test]
+fn rejects_truncated_record() {
+    assert!(parse_message(&[0xD1, 0x01, 0x10, b'T', 0x02]).is_err());
+}
+
+#[test]
+fn reads_employee_id_from_ntag() {
+    let mock = MockTransport::new();
+    let mut reader = ntag_reader(&mock);
+    push_pages(&mock, &[0xE1, 0x10, 0x12, 0x00, 0x01, 0x03, 0xA0, 0x0C, 0x34, 0x03, 0x00, 0xFE, 0, 0, 0, 0]);
+    let data = data_area(&text_message("004217"), 32);
+    push_pages(&mock, &data[..16]);
+    push_pages(&mock, &data[16..]);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.family, CardFamily::MifareUltralight);
+    assert_eq!(card.credential.as_deref(), Some("004217"));
+    assert_eq!(mock.remaining(), 0, "reader kept reading after the message ended");
+    let reads: Vec<u8> = mock.written()[2..].iter().map(|frame| frame[9]).collect();
+    assert_eq!(reads, vec![3, 4, 8]);
+}
+
+#[test]
+fn reads_message_behind_control_tlvs_and_padding() {
+    let mock = MockTransport::new();
+    let mut reader = ntag_reader(&mock);
+    push_pages(&mock, &[0xE1, 0x10, 0x3E, 0x00, 0x01, 0x03, 0xA0, 0x0C, 0x34, 0x02, 0x03, 0x00, 0x10, 0x44, 0x00, 0x00]);
+    let message = text_message("004217");
+    // Lock Control and Memory Control TLVs, then NULL padding to the end of the first read.
+    let mut data = vec![0x01, 0x03, 0xA0, 0x0C, 0x34, 0x02, 0x03, 0x00, 0x10, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    data.extend_from_slice(&[0x03, message.len() as u8]);
+    data.extend_from_slice(&message);
+    data.push(0xFE);
+    data.resize(32, 0x00);
+    push_pages(&mock, &data[..16]);
+    push_pages(&mock, &data[16..]);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.credential.as_deref(), Some("004217"));
+    assert_eq!(mock.remaining(), 0);
+}
+
+#[test]
+fn blank_tag_has_no_credential() {
+    let mock = MockTransport::new();
+    let mut reader = ntag_reader(&mock);
+    push_pages(&mock, &[0x00; 16]);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.uid, NTAG_UID);
+    assert_eq!(card.credential, None);
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::originality::{
+    add_mod, inverse, message_scalar, mul_mod, verify, verify_with_any_key, verify_with_key, Point, GENERATOR, N,
+    NXP_NTAG21X_PUBLIC_KEY, NXP_ULTRALIGHT_EV1_PUBLIC_KEY,
+};
+use urniknet_terminal_lib::reader::pn532::mock::MockTransport;
+use urniknet_terminal_lib::reader::pn532::Pn532Reader;
+use urniknet_terminal_lib::reader::Reader;
+
+const UID: [u8; 7] = [0x04, 0x4F, 0x2A, 0x1B, 0x9C, 0x5D, 0x80];
+const PRIVATE_KEY: u128 = 0x0123_4567_89AB_CDEF_0FED_CBA9_8765_4321;
+
+/// Signs like the NXP factory does, with a test key and a fixed nonce.
+fn sign(uid: &[u8], private_key: u128, nonce: u128) -> Vec<u8> {
+    let r = match GENERATOR.multiply(nonce) {
+        Point::Affine { x, .. } => x % N,
+        Point::Infinity => unreachable!(),
+    };
+    let s = mul_mod(
+        inverse(nonce, N),
+        add_mod(message_scalar(uid), mul_mod(r, private_key, N), N),
+        N,
+    );
+    let mut signature = r.to_be_bytes().to_vec();
+    signature.extend_from_slice(&s.to_be_bytes());
+    signature
+}
+
+#[test]
+fn curve_points_are_valid() {
+    assert!(GENERATOR.is_on_curve());
+    assert!(Point::from_sec1(&NXP_NTAG21X_PUBLIC_KEY).is_some());
+    assert!(Point::from_sec1(&NXP_ULTRALIGHT_EV1_PUBLIC_KEY).is_some());
+    assert_eq!(GENERATOR.multiply(N), Point::Infinity);
+}
+
+#[test]
+fn verifies_signature_over_uid() {
+    let public_key = GENERATOR.multiply(PRIVATE_KEY);
+    let signature = sign(&UID, PRIVATE_KEY, 0x5555_AAAA_1234_5678_9ABC_DEF0_1357_9BDF);
+
+    assert!(verify_with_key(&UID, &signature, &public_key));
+
+    let mut cloned_uid = UID;
+    cloned_uid[6] ^= 0x01;
+    assert!(!verify_with_key(&cloned_uid, &signature, &public_key));
+    let mut tampered = signature.clone();
+    tampered[31] ^= 0x01;
+    assert!(!verify_with_key(&UID, &tampered, &public_key));
+    assert!(!verify(&UID, &signature), "test key must not pass as NXP's");
+}
+
+/// Uncompressed SEC 1 encoding of a test key's public point.
+fn sec1(private_key: u128) -> [u8; 33] {
+    let Point::Affine { x, y } = GENERATOR.multiply(private_key) else {
+        unreachable!()
+    };
+    let mut bytes = [0x04; 33];
+    bytes[1..17].copy_from_slice(&x.to_be_bytes());
+    bytes[17..].copy_from_slice(&y.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn accepts_a_signature_from_either_key() {
+    // Stand-ins for the NTAG21x and the Ultralight EV1 factory keys.
+    const EV1_PRIVATE_KEY: u128 = 0x0F1E_2D3C_4B5A_6978_8796_A5B4_C3D2_E1F0;
+    let keys = [sec1(PRIVATE_KEY), sec1(EV1_PRIVATE_KEY)];
+    let ev1_signature = sign(&UID, EV1_PRIVATE_KEY, 0x2468_ACE0_1357_9BDF_0246_8ACE_1357_9BDF);
+
+    assert!(verify_with_any_key(&UID, &ev1_signature, &keys));
+    assert!(!verify_with_any_key(&UID, &ev1_signature, &keys[..1]));
+    assert!(!verify(&UID, &ev1_signature), "test key must not pass as NXP's");
+}
+
+#[test]
+fn rejects_malformed_signatures() {
+    let public_key = GENERATOR.multiply(PRIVATE_KEY);
+
+    assert!(!verify_with_key(&UID, &[0u8; 32], &public_key));
+    assert!(!verify_with_key(&UID, &[0xFF; 32], &public_key));
+    assert!(!verify_with_key(&UID, &[0x01; 16], &public_key));
+}
+
+#[test]
+fn reader_checks_signature_of_ntag() {
+    let mock = MockTransport::new();
+    mock.push_response(0x14, &[]);
+    let mut target = vec![0x01, 0x01, 0x00, 0x44, 0x00, UID.len() as u8];
+    target.extend_from_slice(&UID);
+    mock.push_response(0x4A, &target);
+    let mut answer = vec![0x00];
+    answer.extend_from_slice(&[0x5A; 32]);
+    mock.push_response(0x40, &answer);
+    let mut reader = Pn532Reader::new(Box::new(mock.clone())).with_originality_check();
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.signature_valid, Some(false));
+    assert_eq!(&mock.written()[2][5..9], &[0xD4, 0x40, 0x01, 0x3C]);
+}
+
+#[test]
+fn tag_refusing_read_sig_is_not_original() {
+    let mock = MockTransport::new();
+    mock.push_response(0x14, &[]);
+    let mut target = vec![0x01, 0x01, 0x00, 0x44, 0x00, UID.len() as u8];
+    target.extend_from_slice(&UID);
+    mock.push_response(0x4A, &target);
+    mock.push_response(0x40, &[0x01]);
+    let mut reader = Pn532Reader::new(Box::new(mock.clone())).with_originality_check();
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.signature_valid, Some(false));
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+
+use urniknet_terminal_lib::config::{AntiPassbackPolicy, PinPolicy};
+use urniknet_terminal_lib::feedback::Outcome;
+use urniknet_terminal_lib::models::Punch;
+use urniknet_terminal_lib::pin;
+use urniknet_terminal_lib::punch_log::log_pin_punch;
+use urniknet_terminal_lib::schema::{cards, punches};
+
+#[test]
+fn hashed_pin_verifies_and_is_salted() {
+    let first = pin::hash("1234").unwrap();
+    let second = pin::hash("1234").unwrap();
+
+    assert!(first.starts_with("pbkdf2-sha256$"));
+    assert_ne!(first, second);
+    assert!(!first.contains("1234$"));
+    assert!(pin::verify(&first, "1234"));
+    assert!(!pin::verify(&first, "1235"));
+}
+
+#[test]
+fn matches_pbkdf2_reference_vector() {
+    // RFC 7914, section 11: PBKDF2-HMAC-SHA256("passwd", "salt", 1).
+    assert_eq!(
+        pin::hash_with_salt("passwd", b"salt", 1),
+        "pbkdf2-sha256$1$73616c74$55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+    );
+}
+
+#[test]
+fn malformed_hashes_never_match() {
+    for stored in ["", "1234", "md5$1$00$00", "pbkdf2-sha256$0$00$00", "pbkdf2-sha256$x$00$00", "pbkdf2-sha256$1$zz$00"] {
+        assert!(!pin::verify(stored, "1234"), "{}", stored);
+    }
+}
+
+#[test]
+fn pins_are_four_to_eight_digits() {
+    assert!(pin::validate("1234").is_ok());
+    assert!(pin::validate("12345678").is_ok());
+    for invalid in ["123", "123456789", "12a4", " 1234", ""] {
+        assert_eq!(pin::validate(invalid), Err(pin::PIN_FORMAT_ERROR.to_string()), "{:?}", invalid);
+        assert!(pin::hash(invalid).is_err());
+    }
+}
+
+#[test]
+fn repeated_failures_lock_the_employee_out() {
+    let policy = PinPolicy {
+        max_failures: 3,
+        lockout_secs: 60,
+    };
+    let now = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_hms_opt(8, 0, 0).unwrap();
+
+    assert_eq!(policy.after_failure(0, now), (1, None));
+    assert_eq!(policy.after_failure(1, now), (2, None));
+    let (failures, locked_until) = policy.after_failure(2, now);
+    assert_eq!(failures, 0);
+    assert!(policy.is_locked(locked_until, now + chrono::Duration::seconds(59)));
+    assert!(!policy.is_locked(locked_until, now + chrono::Duration::seconds(60)));
+    assert!(!policy.is_locked(None, now));
+}
+
+const EMPLOYEE: &str = "E1042";
+
+/// A database with one employee whose PIN is 1234, hashed cheaply to keep the tests fast.
+fn database_with_pin() -> SqliteConnection {
+    let mut conn = common::database();
+    let card = common::add_card(&mut conn, "04:11:22:33", Some(EMPLOYEE));
+    diesel::update(cards::table.filter(cards::id.eq(card.id)))
+        .set(cards::pin_hash.eq(pin::hash_with_salt("1234", b"salt", 1)))
+        .execute(&mut conn)
+        .unwrap();
+    conn
+}
+
+fn punch(conn: &mut SqliteConnection, entered_pin: &str, policy: &PinPolicy) -> Result<Outcome, String> {
+    let anti_passback = AntiPassbackPolicy { min_interval_secs: -1 };
+    log_pin_punch(conn, EMPLOYEE, entered_pin, false, None, policy, &anti_passback).map(|(_, outcome)| outcome)
+}
+
+#[test]
+fn pin_punches_in_and_out_are_both_tagged() {
+    let mut conn = database_with_pin();
+    let policy = PinPolicy::default();
+
+    assert_eq!(punch(&mut conn, "1234", &policy), Ok(Outcome::PunchedIn));
+    assert_eq!(punch(&mut conn, "1234", &policy), Ok(Outcome::PunchedOut));
+
+    let punches = punches::table.order(punches::id).load::<Punch>(&mut conn).unwrap();
+    let recorded: Vec<_> = punches
+        .iter()
+        .map(|punch| (punch.status.as_str(), punch.credential_type.as_deref()))
+        .collect();
+    assert_eq!(recorded, vec![("In", Some(pin::PIN_CREDENTIAL)), ("Out", Some(pin::PIN_CREDENTIAL))]);
+}
+
+#[test]
+fn lockout_holds_against_the_right_pin() {
+    let mut conn = database_with_pin();
+    let policy = PinPolicy {
+        max_failures: 3,
+        lockout_secs: 60,
+    };
+
+    assert_eq!(punch(&mut conn, "0000", &policy), Err(pin::PIN_LOGIN_ERROR.to_string()));
+    assert_eq!(punch(&mut conn, "0000", &policy), Err(pin::PIN_LOGIN_ERROR.to_string()));
+    assert_eq!(punch(&mut conn, "0000", &policy), Err(pin::PIN_LOCKED_ERROR.to_string()));
+    assert_eq!(punch(&mut conn, "1234", &policy), Err(pin::PIN_LOCKED_ERROR.to_string()));
+    assert!(punches::table.load::<Punch>(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn unknown_employee_reads_like_a_wrong_pin() {
+    let mut conn = database_with_pin();
+    let anti_passback = AntiPassbackPolicy { min_interval_secs: -1 };
+
+    let result = log_pin_punch(&mut conn, "E9999", "1234", false, None, &PinPolicy::default(), &anti_passback);
+    assert_eq!(result.err().as_deref(), Some(pin::PIN_LOGIN_ERROR));
+}
@@ -0,0 +1,579 @@
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::pn532::codec::{
+    encode_information, encode_nack, HOST_TO_PN532, PN532_TO_HOST,
+};
+use urniknet_terminal_lib::reader::pn532::mock::{ack_frame, response_frame, MockStep, MockTransport};
+use urniknet_terminal_lib::reader::pn532::classic::{employee_id_from_block, ClassicCredential, KeyType};
+use urniknet_terminal_lib::reader::pn532::{Pn532Reader, COMMUNICATION_TEST_PATTERN};
+use urniknet_terminal_lib::reader::presence::PresenceTracker;
+use urniknet_terminal_lib::reader::{CardFamily, DetectedCard, Reader, ReaderError, ReaderType, TargetType, UidKind};
+
+const UID: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xEF];
+
+/// A reader whose SAMConfiguration handshake has already been scripted.
+fn reader_with(mock: &MockTransport) -> Pn532Reader {
+    mock.push_response(0x14, &[]);
+    Pn532Reader::new(Box::new(mock.clone()))
+}
+
+fn target_response(uid: &[u8]) -> Vec<u8> {
+    let mut data = vec![0x01, 0x01, 0x00, 0x04, 0x08, uid.len() as u8];
+    data.extend_from_slice(uid);
+    data
+}
+
+fn bad_data_checksum() -> Vec<u8> {
+    let mut frame = response_frame(0x4A, &target_response(&UID));
+    let dcs = frame.len() - 2;
+    frame[dcs] = frame[dcs].wrapping_add(1);
+    frame
+}
+
+fn nacks_written(mock: &MockTransport) -> usize {
+    mock.written().iter().filter(|frame| **frame == encode_nack()).count()
+}
+
+fn expect_protocol_error(mock: &MockTransport, reader: &mut Pn532Reader, fragment: &str) {
+    match reader.read_uid(Duration::from_secs(1)) {
+        Err(ReaderError::Protocol(msg)) => {
+            assert!(msg.contains(fragment), "expected '{}' in '{}'", fragment, msg)
+        }
+        other => panic!("expected protocol error '{}', got {:?}", fragment, other),
+    }
+    assert_eq!(mock.remaining(), 0, "script was not fully consumed");
+}
+
+/// Replaces one byte of a valid InListPassiveTarget response.
+fn corrupted_response(index: usize, value: u8) -> Vec<u8> {
+    let mut frame = response_frame(0x4A, &target_response(&UID));
+    frame[index] = value;
+    frame
+}
+
+#[test]
+fn init_wakes_chip_and_configures_sam() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+
+    reader.init().unwrap();
+    reader.init().unwrap();
+
+    assert_eq!(mock.wakeups(), 1);
+    assert_eq!(
+        mock.written(),
+        vec![vec![0x00, 0x00, 0xFF, 0x05, 0xFB, 0xD4, 0x14, 0x01, 0x14, 0x01, 0x02, 0x00]]
+    );
+}
+
+#[test]
+fn read_uid_returns_first_target() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x4A, &target_response(&UID));
+
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), UID.to_vec());
+    assert_eq!(
+        mock.written().last().unwrap(),
+        &vec![0x00, 0x00, 0xFF, 0x04, 0xFC, 0xD4, 0x4A, 0x01, 0x00, 0xE1, 0x00]
+    );
+}
+
+#[test]
+fn read_card_reports_anticollision_data() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    let uid = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+    let mut data = vec![0x01, 0x01, 0x03, 0x44, 0x20, uid.len() as u8];
+    data.extend_from_slice(&uid);
+    data.extend_from_slice(&[0x06, 0x75, 0x77, 0x81, 0x02, 0x80]);
+    mock.push_response(0x4A, &data);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.uid, uid.to_vec());
+    assert_eq!(card.atqa, Some(0x0344));
+    assert_eq!(card.sak, Some(0x20));
+    assert_eq!(card.ats, Some(vec![0x06, 0x75, 0x77, 0x81, 0x02, 0x80]));
+    assert_eq!(card.family, CardFamily::Desfire);
+    assert_eq!(card.reader_type, ReaderType::NFC);
+}
+
+#[test]
+fn classifies_common_card_families() {
+    assert_eq!(CardFamily::classify(0x0004, 0x08), CardFamily::MifareClassic1k);
+    assert_eq!(CardFamily::classify(0x0002, 0x18), CardFamily::MifareClassic4k);
+    assert_eq!(CardFamily::classify(0x0044, 0x00), CardFamily::MifareUltralight);
+    assert_eq!(CardFamily::classify(0x0004, 0x20), CardFamily::IsoDep);
+    assert_eq!(CardFamily::classify(0x0004, 0x01), CardFamily::Unknown);
+}
+
+#[test]
+fn flags_random_uids() {
+    assert_eq!(UidKind::of(&[0x08, 0x12, 0x34, 0x56]), UidKind::Random);
+    assert!(UidKind::of(&[0x08, 0x12, 0x34, 0x56]).is_ephemeral());
+    assert_eq!(UidKind::of(&[0x3F, 0x12, 0x34, 0x56]), UidKind::NonUnique);
+    assert_eq!(UidKind::of(&UID), UidKind::Unique);
+    assert_eq!(UidKind::of(&[0x08, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66]), UidKind::Unique);
+}
+
+#[test]
+fn only_nfc_uids_are_flagged_random() {
+    let uid = vec![0x08, 0x12, 0x34, 0x56];
+    assert_eq!(DetectedCard::from_uid(uid.clone(), CardFamily::Unknown, ReaderType::NFC).uid_kind, UidKind::Random);
+    for reader_type in [ReaderType::Wiegand, ReaderType::RFID, ReaderType::Barcode, ReaderType::Simulated] {
+        assert_eq!(DetectedCard::from_uid(uid.clone(), CardFamily::Unknown, reader_type).uid_kind, UidKind::Unique);
+    }
+}
+
+#[test]
+fn read_uid_keeps_polling_while_field_is_empty() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x4A, &[0x00]);
+    mock.push_response(0x4A, &target_response(&UID));
+
+    assert_eq!(reader.read_uid(Duration::from_secs(2)).unwrap(), UID.to_vec());
+}
+
+#[test]
+fn detection_time_leaves_out_empty_polls() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x4A, &[0x00]);
+    mock.push_response(0x4A, &[0x00]);
+    mock.push_response(0x4A, &target_response(&UID));
+
+    let card = reader.read_card(Duration::from_secs(2)).unwrap();
+
+    // Two empty rounds with their pauses took at least 200 ms before the card answered.
+    assert!(card.detection_ms.unwrap() < 100, "{:?}", card.detection_ms);
+}
+
+#[test]
+fn missing_ack_times_out() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_timeout();
+
+    assert!(matches!(
+        reader.read_uid(Duration::from_secs(1)),
+        Err(ReaderError::Timeout)
+    ));
+}
+
+#[test]
+fn bus_failure_is_a_device_error() {
+    let mock = MockTransport::new();
+    mock.push(MockStep::DeviceError("Remote I/O error".into()));
+    let mut reader = Pn532Reader::new(Box::new(mock.clone()));
+
+    assert!(matches!(reader.init(), Err(ReaderError::Device(_))));
+}
+
+#[test]
+fn rejects_unexpected_ack() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_frame(vec![0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00]);
+
+    expect_protocol_error(&mock, &mut reader, "Unexpected ACK frame");
+}
+
+#[test]
+fn rejects_unexpected_header() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_ack();
+    mock.push_frame(corrupted_response(2, 0xFE));
+
+    expect_protocol_error(&mock, &mut reader, "Unexpected response header");
+}
+
+#[test]
+fn rejects_bad_length_checksum_after_retransmits() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_ack();
+    for _ in 0..3 {
+        mock.push_frame(corrupted_response(4, 0x00));
+    }
+
+    expect_protocol_error(&mock, &mut reader, "Invalid length checksum");
+    assert_eq!(nacks_written(&mock), 2);
+}
+
+#[test]
+fn rejects_frame_without_command_code() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_ack();
+    mock.push_frame(vec![0x00, 0x00, 0xFF, 0x01, 0xFF, 0xD5, 0x2B, 0x00]);
+
+    expect_protocol_error(&mock, &mut reader, "Response frame too short");
+}
+
+#[test]
+fn rejects_unexpected_frame_identifier() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    let mut payload = vec![0x4B];
+    payload.extend_from_slice(&target_response(&UID));
+    mock.push_ack();
+    mock.push_frame(encode_information(HOST_TO_PN532, &payload).unwrap());
+
+    expect_protocol_error(&mock, &mut reader, "Unexpected frame identifier");
+}
+
+#[test]
+fn rejects_unexpected_response_code() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x14, &[]);
+
+    expect_protocol_error(&mock, &mut reader, "Unexpected response code");
+}
+
+#[test]
+fn rejects_bad_data_checksum_after_retransmits() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_ack();
+    for _ in 0..3 {
+        mock.push_frame(bad_data_checksum());
+    }
+
+    expect_protocol_error(&mock, &mut reader, "Invalid data checksum");
+    assert_eq!(nacks_written(&mock), 2);
+}
+
+#[test]
+fn bad_checksum_is_recovered_with_nack() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_ack();
+    mock.push_frame(bad_data_checksum());
+    mock.push_frame(response_frame(0x4A, &target_response(&UID)));
+
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), UID.to_vec());
+    assert_eq!(mock.written().last().unwrap(), &encode_nack());
+}
+
+#[test]
+fn rejects_syntax_error_frame() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_ack();
+    mock.push_frame(vec![0x00, 0x00, 0xFF, 0x01, 0xFF, 0x7F, 0x81, 0x00]);
+
+    expect_protocol_error(&mock, &mut reader, "syntax error frame");
+}
+
+#[test]
+fn accepts_extended_response_frame() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    let mut data = target_response(&UID);
+    data.resize(300, 0xAB);
+    let mut payload = vec![0x4B];
+    payload.extend_from_slice(&data[..262]);
+    mock.push_ack();
+    mock.push_frame(encode_information(PN532_TO_HOST, &payload).unwrap());
+
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), UID.to_vec());
+}
+
+#[test]
+fn rejects_missing_postamble() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    let mut frame = response_frame(0x4A, &target_response(&UID));
+    let postamble = frame.len() - 1;
+    frame[postamble] = 0xFF;
+    mock.push_ack();
+    mock.push_frame(frame);
+
+    expect_protocol_error(&mock, &mut reader, "Missing postamble");
+}
+
+#[test]
+fn rejects_truncated_frame() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    let mut frame = response_frame(0x4A, &target_response(&UID));
+    frame.truncate(frame.len() - 3);
+    mock.push_ack();
+    mock.push_frame(frame);
+
+    expect_protocol_error(&mock, &mut reader, "Read past the end");
+}
+
+#[test]
+fn rejects_incomplete_target_data() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x4A, &[0x01, 0x01, 0x00, 0x04]);
+
+    expect_protocol_error(&mock, &mut reader, "Incomplete InListPassiveTarget response");
+}
+
+#[test]
+fn rejects_uid_longer_than_response() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x4A, &[0x01, 0x01, 0x00, 0x04, 0x08, 0x07, 0xDE, 0xAD]);
+
+    expect_protocol_error(&mock, &mut reader, "Invalid UID length");
+}
+
+fn classic_reader(mock: &MockTransport) -> Pn532Reader {
+    let credential = ClassicCredential::new(4, KeyType::B, "A0A1A2A3A4A5").unwrap();
+    reader_with(mock).with_classic_credential(credential)
+}
+
+#[test]
+fn reads_employee_number_from_classic_block() {
+    let mock = MockTransport::new();
+    let mut reader = classic_reader(&mock);
+    mock.push_response(0x4A, &target_response(&UID));
+    mock.push_response(0x40, &[0x00]);
+    let mut block = vec![0x00];
+    block.extend_from_slice(b"  004217\0\0\0\0\0\0\0\0");
+    mock.push_response(0x40, &block);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.credential.as_deref(), Some("004217"));
+    let written = mock.written();
+    assert_eq!(
+        &written[2][5..19],
+        &[0xD4, 0x40, 0x01, 0x61, 0x04, 0xA0, 0xA1, 0xA2, 0xA3, 0xA4, 0xA5, 0xDE, 0xAD, 0xBE]
+    );
+    assert_eq!(&written[3][5..10], &[0xD4, 0x40, 0x01, 0x30, 0x04]);
+}
+
+#[test]
+fn card_refusing_the_key_has_no_credential() {
+    let mock = MockTransport::new();
+    let mut reader = classic_reader(&mock);
+    mock.push_response(0x4A, &target_response(&UID));
+    mock.push_response(0x40, &[0x14]);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.uid, UID);
+    assert_eq!(card.credential, None);
+    assert_eq!(mock.remaining(), 0);
+}
+
+#[test]
+fn rejects_invalid_classic_configuration() {
+    assert!(ClassicCredential::new(4, KeyType::A, "FFFF").is_err());
+    assert!(ClassicCredential::new(4, KeyType::A, "GGGGGGGGGGGG").is_err());
+    assert!(ClassicCredential::new(7, KeyType::A, "FFFFFFFFFFFF").is_err());
+    assert_eq!(employee_id_from_block(&[0xFF; 16]), None);
+}
+
+fn auto_poll_reader(mock: &MockTransport) -> Pn532Reader {
+    reader_with(mock).with_auto_poll(Vec::new())
+}
+
+#[test]
+fn auto_poll_reports_type_a_target() {
+    let mock = MockTransport::new();
+    let mut reader = auto_poll_reader(&mock);
+    let mut target = vec![0x01, 0x00, 0x04, 0x08, UID.len() as u8];
+    target.extend_from_slice(&UID);
+    let mut response = vec![0x01, 0x10, target.len() as u8];
+    response.extend_from_slice(&target);
+    mock.push_response(0x60, &response);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(card.uid, UID);
+    assert_eq!(card.family, CardFamily::MifareClassic1k);
+    assert_eq!(card.target_type, Some(TargetType::Iso14443A));
+    assert!(card.detection_ms.is_some());
+    assert_eq!(&mock.written()[1][5..12], &[0xD4, 0x60, 0xFF, 0x01, 0x00, 0x11, 0x23]);
+}
+
+#[test]
+fn auto_poll_reports_felica_and_type_b() {
+    let nfcid2 = [0x01, 0x2E, 0x3C, 0x4D, 0x5E, 0x6F, 0x70, 0x81];
+    let mut felica = vec![0x01, 0x11, 0x13, 0x01, 0x12, 0x01];
+    felica.extend_from_slice(&nfcid2);
+    felica.extend_from_slice(&[0x00; 10]);
+    let pupi = [0x9A, 0xBC, 0xDE, 0xF0];
+    let mut type_b = vec![0x01, 0x23, 0x0F, 0x01, 0x50];
+    type_b.extend_from_slice(&pupi);
+    type_b.extend_from_slice(&[0x00; 7]);
+    type_b.extend_from_slice(&[0x01, 0x00, 0x00]);
+
+    for (response, uid, target_type, family) in [
+        (felica, nfcid2.to_vec(), TargetType::Felica212, CardFamily::Felica),
+        (type_b, pupi.to_vec(), TargetType::Iso14443B, CardFamily::IsoDep),
+    ] {
+        let mock = MockTransport::new();
+        let mut reader = auto_poll_reader(&mock);
+        mock.push_response(0x60, &response);
+
+        let card = reader.read_card(Duration::from_secs(1)).unwrap();
+
+        assert_eq!(card.uid, uid);
+        assert_eq!(card.target_type, Some(target_type));
+        assert_eq!(card.family, family);
+    }
+}
+
+#[test]
+fn auto_poll_is_aborted_when_nothing_answers() {
+    let mock = MockTransport::new();
+    let mut reader = auto_poll_reader(&mock);
+    mock.push_ack();
+    mock.push_timeout();
+
+    assert!(matches!(
+        reader.read_card(Duration::from_millis(200)),
+        Err(ReaderError::Timeout)
+    ));
+    assert_eq!(mock.written().last(), Some(&ack_frame()));
+}
+
+#[test]
+fn auto_poll_rejects_unknown_target_type() {
+    let mock = MockTransport::new();
+    let mut reader = auto_poll_reader(&mock);
+    mock.push_response(0x60, &[0x01, 0x42, 0x01, 0x01]);
+
+    match reader.read_card(Duration::from_secs(1)) {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("Unsupported InAutoPoll target type"), "{}", msg),
+        other => panic!("expected a protocol error, got {:?}", other),
+    }
+}
+
+/// A reader that has just activated an ISO 14443-4 target.
+fn reader_with_iso_dep_target(mock: &MockTransport) -> Pn532Reader {
+    let mut reader = reader_with(mock);
+    let mut target = vec![0x01, 0x01, 0x03, 0x44, 0x20, UID.len() as u8];
+    target.extend_from_slice(&UID);
+    mock.push_response(0x4A, &target);
+    reader.read_card(Duration::from_secs(1)).unwrap();
+    reader
+}
+
+#[test]
+fn card_present_while_target_answers_diagnose() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with_iso_dep_target(&mock);
+    mock.push_response(0x00, &[0x00]);
+
+    assert_eq!(reader.card_present().unwrap(), Some(true));
+    assert_eq!(
+        mock.written().last().unwrap(),
+        &encode_information(HOST_TO_PN532, &[0x00, 0x06]).unwrap()
+    );
+}
+
+#[test]
+fn removed_card_is_released() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with_iso_dep_target(&mock);
+    mock.push_response(0x00, &[0x01]);
+    mock.push_response(0x52, &[0x00]);
+
+    assert_eq!(reader.card_present().unwrap(), Some(false));
+    assert_eq!(
+        mock.written().last().unwrap(),
+        &encode_information(HOST_TO_PN532, &[0x52, 0x00]).unwrap()
+    );
+    // Nothing is activated any more, so there is nothing to ask.
+    assert_eq!(reader.card_present().unwrap(), None);
+}
+
+#[test]
+fn resting_classic_card_is_reported_once() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    let mut presence = PresenceTracker::default();
+    for _ in 0..4 {
+        mock.push_response(0x4A, &target_response(&UID));
+    }
+
+    let mut arrivals = 0;
+    for _ in 0..4 {
+        // As in the worker: ask the reader first, and poll again when it cannot tell.
+        if presence.current().is_some() {
+            assert_eq!(reader.card_present().unwrap(), None);
+        }
+        let card = reader.read_card(Duration::from_secs(1)).unwrap();
+        if presence.card_seen(&card.uid) {
+            arrivals += 1;
+        }
+    }
+
+    assert_eq!(arrivals, 1);
+    assert_eq!(mock.remaining(), 0);
+    let diagnose = encode_information(HOST_TO_PN532, &[0x00, 0x06]).unwrap();
+    assert!(!mock.written().contains(&diagnose), "Diagnose sent to a MIFARE Classic card");
+}
+
+fn communication_test_echo() -> Vec<u8> {
+    let mut echo = vec![0x00];
+    echo.extend_from_slice(&COMMUNICATION_TEST_PATTERN);
+    echo
+}
+
+#[test]
+fn diagnostics_report_firmware_and_self_test() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x02, &[0x32, 0x01, 0x06, 0x07]);
+    mock.push_response(0x00, &communication_test_echo());
+
+    let diagnostics = reader.diagnostics().unwrap();
+
+    let firmware = diagnostics.firmware.unwrap();
+    assert_eq!((firmware.ic, firmware.version, firmware.revision), (0x32, 0x01, 0x06));
+    assert_eq!(firmware.protocols, vec!["ISO/IEC 14443 A", "ISO/IEC 14443 B", "ISO 18092"]);
+    assert_eq!(diagnostics.communication_test, Some(true));
+    assert_eq!(diagnostics.device_path.as_deref(), Some("mock"));
+    assert_eq!(diagnostics.transport.as_deref(), Some("i2c"));
+}
+
+#[test]
+fn diagnostics_flag_corrupted_echo() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x02, &[0x32, 0x01, 0x06, 0x07]);
+    let mut echo = communication_test_echo();
+    echo[3] ^= 0x10;
+    mock.push_response(0x00, &echo);
+
+    assert_eq!(reader.diagnostics().unwrap().communication_test, Some(false));
+}
+
+#[test]
+fn rejects_short_firmware_version() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x02, &[0x32, 0x01]);
+
+    match reader.diagnostics() {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("GetFirmwareVersion"), "{}", msg),
+        other => panic!("expected a protocol error, got {:?}", other),
+    }
+}
+
+#[test]
+fn write_gpio_validates_only_p3() {
+    let mock = MockTransport::new();
+    let mut reader = reader_with(&mock);
+    mock.push_response(0x0E, &[]);
+
+    reader.write_gpio(0b01_0010).unwrap();
+
+    assert_eq!(
+        mock.written().last().unwrap(),
+        &encode_information(HOST_TO_PN532, &[0x0E, 0x92, 0x00]).unwrap()
+    );
+}
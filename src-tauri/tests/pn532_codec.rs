@@ -0,0 +1,92 @@
+use urniknet_terminal_lib::reader::pn532::codec::{
+    decode, encode_ack, encode_command, encode_information, encode_nack, frame_length, Frame,
+    FrameError, HOST_TO_PN532, PN532_TO_HOST,
+};
+
+#[test]
+fn encodes_normal_command_frame() {
+    assert_eq!(
+        encode_command(0x02, &[]).unwrap(),
+        vec![0x00, 0x00, 0xFF, 0x02, 0xFE, 0xD4, 0x02, 0x2A, 0x00]
+    );
+}
+
+#[test]
+fn normal_frame_round_trips() {
+    let frame = encode_information(PN532_TO_HOST, &[0x4B, 0x01, 0x02, 0x03]).unwrap();
+
+    assert_eq!(frame_length(&frame[..5]).unwrap(), Some(frame.len()));
+    assert_eq!(
+        decode(&frame).unwrap(),
+        Frame::Information {
+            tfi: PN532_TO_HOST,
+            data: vec![0x4B, 0x01, 0x02, 0x03]
+        }
+    );
+}
+
+#[test]
+fn long_payload_uses_extended_frame() {
+    let data = vec![0x5A; 260 - 1];
+    let frame = encode_information(HOST_TO_PN532, &data).unwrap();
+
+    assert_eq!(&frame[..5], &[0x00, 0x00, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(&frame[5..8], &[0x01, 0x04, 0xFB]);
+    assert_eq!(frame_length(&frame[..5]).unwrap(), None);
+    assert_eq!(frame_length(&frame[..8]).unwrap(), Some(frame.len()));
+    assert_eq!(
+        decode(&frame).unwrap(),
+        Frame::Information {
+            tfi: HOST_TO_PN532,
+            data
+        }
+    );
+}
+
+#[test]
+fn rejects_payload_beyond_extended_limit() {
+    assert_eq!(
+        encode_information(HOST_TO_PN532, &[0u8; 265]),
+        Err(FrameError::PayloadTooLong(266))
+    );
+}
+
+#[test]
+fn decodes_control_frames() {
+    assert_eq!(decode(&encode_ack()).unwrap(), Frame::Ack);
+    assert_eq!(decode(&encode_nack()).unwrap(), Frame::Nack);
+    assert_eq!(
+        decode(&[0x00, 0x00, 0xFF, 0x01, 0xFF, 0x7F, 0x81, 0x00]).unwrap(),
+        Frame::Error
+    );
+}
+
+#[test]
+fn reports_typed_errors() {
+    let frame = encode_information(PN532_TO_HOST, &[0x03, 0x32]).unwrap();
+
+    let mut bad_start = frame.clone();
+    bad_start[1] = 0x01;
+    assert_eq!(decode(&bad_start), Err(FrameError::BadStartCode([0x00, 0x01, 0xFF])));
+
+    let mut bad_lcs = frame.clone();
+    bad_lcs[4] ^= 0x01;
+    assert_eq!(decode(&bad_lcs), Err(FrameError::LengthChecksum));
+
+    let mut bad_dcs = frame.clone();
+    bad_dcs[frame.len() - 2] ^= 0x01;
+    assert_eq!(decode(&bad_dcs), Err(FrameError::DataChecksum));
+    assert!(FrameError::DataChecksum.is_retransmittable());
+
+    let mut bad_postamble = frame.clone();
+    bad_postamble[frame.len() - 1] = 0xAA;
+    assert_eq!(decode(&bad_postamble), Err(FrameError::MissingPostamble(0xAA)));
+
+    assert_eq!(
+        decode(&frame[..frame.len() - 1]),
+        Err(FrameError::Truncated {
+            expected: frame.len(),
+            actual: frame.len() - 1
+        })
+    );
+}
@@ -0,0 +1,54 @@
+use urniknet_terminal_lib::reader::presence::PresenceTracker;
+
+const CARD: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xEF];
+const OTHER: [u8; 4] = [0x04, 0x11, 0x22, 0x33];
+
+#[test]
+fn resting_card_is_reported_once() {
+    let mut tracker = PresenceTracker::new(2);
+
+    assert!(tracker.card_seen(&CARD));
+    assert!(!tracker.card_seen(&CARD));
+    assert!(!tracker.card_seen(&CARD));
+    assert_eq!(tracker.current(), Some(&CARD[..]));
+}
+
+#[test]
+fn card_counts_as_removed_after_missed_polls() {
+    let mut tracker = PresenceTracker::new(2);
+    tracker.card_seen(&CARD);
+
+    assert_eq!(tracker.poll_empty(), None);
+    assert_eq!(tracker.poll_empty(), Some(CARD.to_vec()));
+    assert_eq!(tracker.current(), None);
+    assert!(tracker.card_seen(&CARD));
+}
+
+#[test]
+fn single_missed_poll_does_not_remove_card() {
+    let mut tracker = PresenceTracker::new(2);
+    tracker.card_seen(&CARD);
+
+    assert_eq!(tracker.poll_empty(), None);
+    assert!(!tracker.card_seen(&CARD));
+    assert_eq!(tracker.poll_empty(), None);
+}
+
+#[test]
+fn another_card_is_reported_immediately() {
+    let mut tracker = PresenceTracker::default();
+    tracker.card_seen(&CARD);
+
+    assert!(tracker.card_seen(&OTHER));
+    assert_eq!(tracker.current(), Some(&OTHER[..]));
+}
+
+#[test]
+fn direct_removal_forgets_card() {
+    let mut tracker = PresenceTracker::default();
+    tracker.card_seen(&CARD);
+
+    assert_eq!(tracker.mark_removed(), Some(CARD.to_vec()));
+    assert_eq!(tracker.poll_empty(), None);
+    assert!(tracker.card_seen(&CARD));
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use diesel::prelude::*;
+
+use urniknet_terminal_lib::config::{AntiPassbackPolicy, ReaderRole};
+use urniknet_terminal_lib::feedback::Outcome;
+use urniknet_terminal_lib::models::{Card, Punch};
+use urniknet_terminal_lib::punch_log::{log_punch, PunchSource};
+use urniknet_terminal_lib::schema::{cards, punches};
+
+const SOURCE: PunchSource = PunchSource {
+    credential_type: Some("mifare_classic_1k"),
+    reader_name: Some("door"),
+};
+
+fn database_with_card() -> (SqliteConnection, Card) {
+    let mut conn = common::database();
+    let card = common::add_card(&mut conn, "de:ad:be:ef", None);
+    (conn, card)
+}
+
+fn no_anti_passback() -> AntiPassbackPolicy {
+    AntiPassbackPolicy { min_interval_secs: -1 }
+}
+
+fn statuses(conn: &mut SqliteConnection) -> Vec<String> {
+    punches::table
+        .order(punches::id)
+        .load::<Punch>(conn)
+        .unwrap()
+        .into_iter()
+        .map(|punch| punch.status)
+        .collect()
+}
+
+#[test]
+fn records_the_punch_with_its_source() {
+    let (mut conn, card) = database_with_card();
+
+    let outcome = log_punch(&mut conn, &card, ReaderRole::Toggle, false, None, SOURCE, &no_anti_passback());
+
+    assert_eq!(outcome, Outcome::PunchedIn);
+    let punch = punches::table.first::<Punch>(&mut conn).unwrap();
+    assert_eq!(punch.status, "In");
+    assert_eq!(punch.credential_type.as_deref(), Some("mifare_classic_1k"));
+    assert_eq!(punch.reader_name.as_deref(), Some("door"));
+}
+
+#[test]
+fn punch_held_back_by_anti_passback_is_not_confirmed() {
+    let (mut conn, card) = database_with_card();
+    let anti_passback = AntiPassbackPolicy { min_interval_secs: 60 };
+
+    assert_eq!(log_punch(&mut conn, &card, ReaderRole::Entry, false, None, SOURCE, &anti_passback), Outcome::PunchedIn);
+    assert_eq!(
+        log_punch(&mut conn, &card, ReaderRole::Exit, false, None, SOURCE, &anti_passback),
+        Outcome::AlreadyRecorded
+    );
+    assert_eq!(statuses(&mut conn), vec!["In"]);
+}
+
+#[test]
+fn toggle_tap_of_a_card_already_in_is_not_confirmed() {
+    let (mut conn, card) = database_with_card();
+    let policy = no_anti_passback();
+
+    assert_eq!(log_punch(&mut conn, &card, ReaderRole::Toggle, false, None, SOURCE, &policy), Outcome::PunchedIn);
+    assert_eq!(
+        log_punch(&mut conn, &card, ReaderRole::Toggle, false, None, SOURCE, &policy),
+        Outcome::AlreadyRecorded
+    );
+    assert_eq!(statuses(&mut conn), vec!["In"]);
+}
+
+#[test]
+fn entry_exit_and_leave_report_what_was_recorded() {
+    let (mut conn, card) = database_with_card();
+    let policy = no_anti_passback();
+
+    assert_eq!(log_punch(&mut conn, &card, ReaderRole::Entry, false, None, SOURCE, &policy), Outcome::PunchedIn);
+    assert_eq!(log_punch(&mut conn, &card, ReaderRole::Exit, false, None, SOURCE, &policy), Outcome::PunchedOut);
+    assert_eq!(
+        log_punch(&mut conn, &card, ReaderRole::LeaveOnly, true, Some("leave_type.lunch"), SOURCE, &policy),
+        Outcome::LeaveRegistered
+    );
+    assert_eq!(statuses(&mut conn), vec!["In", "Out", "leave_type.lunch"]);
+    assert!(!cards::table.first::<Card>(&mut conn).unwrap().is_present);
+}
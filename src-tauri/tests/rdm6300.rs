@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::io::FromRawFd;
+use std::thread;
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::rdm6300::{parse_frame, Rdm6300};
+use urniknet_terminal_lib::reader::{CardFamily, Reader, ReaderError, ReaderType};
+
+/// Frame of tag 62E3086CED, as sent by a module with a badge in the field.
+const FRAME: &[u8; 14] = b"\x0262E3086CED08\x03";
+const TAG: [u8; 5] = [0x62, 0xE3, 0x08, 0x6C, 0xED];
+/// Frame of tag 0102030405.
+const OTHER_FRAME: &[u8; 14] = b"\x02010203040501\x03";
+
+/// A pseudo-terminal pair: the reader opens the slave path, the test writes to the master
+/// as the module would.
+fn pty() -> (File, String) {
+    let mut master = 0;
+    let mut slave = 0;
+    let mut name = [0 as libc::c_char; 64];
+    let result = unsafe {
+        libc::openpty(&mut master, &mut slave, name.as_mut_ptr(), std::ptr::null(), std::ptr::null())
+    };
+    assert_eq!(result, 0, "openpty failed: {}", std::io::Error::last_os_error());
+    let path = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }.to_string_lossy().into_owned();
+    // The reader opens the slave by path; the master keeps the pair alive.
+    unsafe { libc::close(slave) };
+    (unsafe { File::from_raw_fd(master) }, path)
+}
+
+fn opened_reader() -> (File, Rdm6300) {
+    let (master, path) = pty();
+    let mut reader = Rdm6300::new(path);
+    reader.init().unwrap();
+    (master, reader)
+}
+
+fn send(master: &mut File, bytes: &[u8]) {
+    master.write_all(bytes).unwrap();
+    master.flush().unwrap();
+}
+
+#[test]
+fn parses_recorded_frame() {
+    assert_eq!(parse_frame(FRAME).unwrap(), TAG);
+    assert_eq!(parse_frame(OTHER_FRAME).unwrap(), [0x01, 0x02, 0x03, 0x04, 0x05]);
+}
+
+#[test]
+fn rejects_bad_checksum() {
+    match parse_frame(b"\x0262E3086CED09\x03") {
+        Err(ReaderError::Protocol(msg)) => assert!(msg.contains("checksum"), "{}", msg),
+        other => panic!("expected a checksum error, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_bad_delimiters_and_digits() {
+    assert!(matches!(parse_frame(b"\x0162E3086CED08\x03"), Err(ReaderError::Protocol(_))));
+    assert!(matches!(parse_frame(b"\x0262E3086CEZ08\x03"), Err(ReaderError::Protocol(_))));
+    assert!(matches!(parse_frame(&FRAME[..13]), Err(ReaderError::Protocol(_))));
+}
+
+#[test]
+fn reads_tag_from_serial_port() {
+    let (mut master, mut reader) = opened_reader();
+    send(&mut master, FRAME);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+    assert_eq!(card.uid, TAG.to_vec());
+    assert_eq!(card.family, CardFamily::Em4100);
+    assert_eq!(card.reader_type, ReaderType::RFID);
+}
+
+#[test]
+fn resynchronises_after_noise_and_bad_frames() {
+    let (mut master, mut reader) = opened_reader();
+    let mut stream = b"\xFF\x00noise\x02123".to_vec();
+    stream.extend_from_slice(b"\x0262E3086CED09\x03");
+    stream.extend_from_slice(OTHER_FRAME);
+    send(&mut master, &stream);
+
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), vec![0x01, 0x02, 0x03, 0x04, 0x05]);
+}
+
+#[test]
+fn joins_frame_split_across_reads() {
+    let (mut master, mut reader) = opened_reader();
+    send(&mut master, &FRAME[..5]);
+    let writer = thread::spawn(move || {
+        // Longer than one read slice, so the halves arrive in separate reads.
+        thread::sleep(Duration::from_millis(250));
+        send(&mut master, &FRAME[5..]);
+        master
+    });
+
+    assert_eq!(reader.read_uid(Duration::from_secs(2)).unwrap(), TAG.to_vec());
+    writer.join().unwrap();
+}
+
+#[test]
+fn drops_repeats_until_the_tag_has_left() {
+    let (mut master, mut reader) = opened_reader();
+    send(&mut master, FRAME);
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), TAG.to_vec());
+
+    send(&mut master, FRAME);
+    assert!(matches!(reader.read_uid(Duration::from_millis(300)), Err(ReaderError::Timeout)));
+
+    // A different tag is not a repeat.
+    send(&mut master, OTHER_FRAME);
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), vec![0x01, 0x02, 0x03, 0x04, 0x05]);
+
+    send(&mut master, OTHER_FRAME);
+    assert!(matches!(reader.read_uid(Duration::from_millis(300)), Err(ReaderError::Timeout)));
+    thread::sleep(Duration::from_millis(600));
+    send(&mut master, OTHER_FRAME);
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), vec![0x01, 0x02, 0x03, 0x04, 0x05]);
+}
+
+#[test]
+fn unplugged_port_is_a_device_error() {
+    let (master, mut reader) = opened_reader();
+    drop(master);
+
+    match reader.read_uid(Duration::from_secs(1)) {
+        Err(ReaderError::Device(msg)) => assert!(msg.contains("disconnected"), "{}", msg),
+        other => panic!("expected a device error, got {:?}", other),
+    }
+}
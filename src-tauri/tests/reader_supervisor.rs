@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::supervisor::{ReaderHealth, ReaderState, SupervisedReader};
+use urniknet_terminal_lib::reader::{Reader, ReaderError, ReaderType};
+
+type Script = Arc<Mutex<VecDeque<Result<Vec<u8>, ReaderError>>>>;
+
+/// Reader that replays scripted `read_uid` results.
+struct ScriptedReader {
+    script: Script,
+}
+
+impl Reader for ScriptedReader {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        Ok(())
+    }
+
+    fn read_uid(&mut self, _timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.script
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(Err(ReaderError::Timeout))
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::NFC
+    }
+}
+
+/// A supervisor whose factory counts how often it had to open the device.
+fn supervised(script: &Script, opens: &Arc<Mutex<usize>>) -> SupervisedReader {
+    let script = script.clone();
+    let opens = opens.clone();
+    let health = Arc::new(Mutex::new(ReaderHealth::new(ReaderType::NFC)));
+    SupervisedReader::new(
+        ReaderType::NFC,
+        Box::new(move || {
+            *opens.lock().unwrap() += 1;
+            Ok(Box::new(ScriptedReader {
+                script: script.clone(),
+            }) as Box<dyn Reader>)
+        }),
+        health,
+    )
+    .with_backoff(Duration::from_millis(20), Duration::from_millis(80))
+}
+
+#[test]
+fn device_error_reopens_after_backoff() {
+    let script: Script = Default::default();
+    let opens = Arc::new(Mutex::new(0));
+    let mut reader = supervised(&script, &opens);
+    script.lock().unwrap().extend([
+        Err(ReaderError::Device("Remote I/O error".into())),
+        Ok(vec![0x01, 0x02, 0x03, 0x04]),
+    ]);
+
+    assert!(matches!(reader.read_uid(Duration::ZERO), Err(ReaderError::Device(_))));
+    assert_eq!(reader.health().state, ReaderState::Offline);
+
+    // Still inside the backoff window: the device is not touched.
+    assert!(matches!(reader.read_uid(Duration::ZERO), Err(ReaderError::Device(_))));
+    assert_eq!(*opens.lock().unwrap(), 1);
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(reader.read_uid(Duration::ZERO).unwrap(), vec![0x01, 0x02, 0x03, 0x04]);
+    assert_eq!(*opens.lock().unwrap(), 2);
+
+    let health = reader.health();
+    assert_eq!(health.state, ReaderState::Healthy);
+    assert_eq!(health.reconnects, 1);
+    assert_eq!(health.consecutive_failures, 0);
+}
+
+#[test]
+fn protocol_errors_degrade_then_reopen() {
+    let script: Script = Default::default();
+    let opens = Arc::new(Mutex::new(0));
+    let mut reader = supervised(&script, &opens);
+    for _ in 0..3 {
+        script
+            .lock()
+            .unwrap()
+            .push_back(Err(ReaderError::Protocol("Invalid data checksum".into())));
+    }
+
+    assert!(reader.read_uid(Duration::ZERO).is_err());
+    assert_eq!(reader.health().state, ReaderState::Degraded);
+    assert!(reader.read_uid(Duration::ZERO).is_err());
+    assert!(reader.read_uid(Duration::ZERO).is_err());
+    assert_eq!(reader.health().state, ReaderState::Offline);
+    assert_eq!(*opens.lock().unwrap(), 1);
+}
+
+#[test]
+fn timeouts_keep_reader_healthy() {
+    let script: Script = Default::default();
+    let opens = Arc::new(Mutex::new(0));
+    let mut reader = supervised(&script, &opens);
+
+    assert!(matches!(reader.read_uid(Duration::ZERO), Err(ReaderError::Timeout)));
+    assert!(matches!(reader.read_uid(Duration::ZERO), Err(ReaderError::Timeout)));
+
+    assert_eq!(reader.health().state, ReaderState::Healthy);
+    assert_eq!(*opens.lock().unwrap(), 1);
+}
+
+#[test]
+fn diagnostics_count_reads_and_keep_last_error() {
+    let script: Script = Default::default();
+    let opens = Arc::new(Mutex::new(0));
+    let mut reader = supervised(&script, &opens);
+    script.lock().unwrap().extend([
+        Ok(vec![0x01, 0x02, 0x03, 0x04]),
+        Err(ReaderError::Timeout),
+        Err(ReaderError::Protocol("Invalid data checksum".into())),
+        Ok(vec![0x01, 0x02, 0x03, 0x04]),
+    ]);
+    for _ in 0..4 {
+        let _ = reader.read_uid(Duration::ZERO);
+    }
+
+    let diagnostics = reader.diagnostics().unwrap();
+    assert_eq!(diagnostics.reader_type, ReaderType::NFC);
+    assert_eq!(diagnostics.successful_reads, 2);
+    assert_eq!(diagnostics.failed_reads, 1);
+    assert!(diagnostics.last_error.unwrap().contains("Invalid data checksum"));
+}
@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use urniknet_terminal_lib::reader::{Reader, ReaderError, ReaderType};
+use urniknet_terminal_lib::readers::{SharedReader, POLL_SLICE};
+
+type Log<T> = Arc<Mutex<Vec<T>>>;
+
+/// Reader with no card in the field: every poll takes its whole timeout.
+struct EmptyField {
+    polls: Log<Duration>,
+    gpio: Log<u8>,
+}
+
+impl Reader for EmptyField {
+    fn init(&mut self) -> Result<(), ReaderError> {
+        Ok(())
+    }
+
+    fn read_uid(&mut self, timeout: Duration) -> Result<Vec<u8>, ReaderError> {
+        self.polls.lock().unwrap().push(timeout);
+        thread::sleep(timeout);
+        Err(ReaderError::Timeout)
+    }
+
+    fn write_gpio(&mut self, levels: u8) -> Result<(), ReaderError> {
+        self.gpio.lock().unwrap().push(levels);
+        Ok(())
+    }
+
+    fn get_reader_type(&self) -> ReaderType {
+        ReaderType::NFC
+    }
+}
+
+fn shared() -> (SharedReader<EmptyField>, Log<Duration>, Log<u8>) {
+    let polls = Arc::new(Mutex::new(Vec::new()));
+    let gpio = Arc::new(Mutex::new(Vec::new()));
+    let reader = EmptyField {
+        polls: polls.clone(),
+        gpio: gpio.clone(),
+    };
+    (SharedReader::new(reader), polls, gpio)
+}
+
+#[test]
+fn long_poll_holds_the_reader_in_slices() {
+    let (reader, polls, _) = shared();
+
+    let start = Instant::now();
+    assert!(matches!(reader.read_card(Duration::from_millis(300)), Err(ReaderError::Timeout)));
+
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    let polls = polls.lock().unwrap();
+    assert!(polls.len() >= 6, "{:?}", polls);
+    assert!(polls.iter().all(|slice| *slice <= POLL_SLICE));
+}
+
+#[test]
+fn feedback_gets_through_while_a_poll_is_in_progress() {
+    let (reader, _, gpio) = shared();
+    let stop = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            while !stop.load(Ordering::Relaxed) {
+                let _ = reader.read_card(Duration::from_secs(1));
+            }
+        });
+        thread::sleep(Duration::from_millis(30));
+        for levels in [0b01, 0b00, 0b11, 0b00, 0b01, 0b00] {
+            let started = Instant::now();
+            reader.write_gpio(levels).unwrap();
+            assert!(started.elapsed() < POLL_SLICE * 3, "write waited {:?}", started.elapsed());
+            thread::sleep(Duration::from_millis(20));
+        }
+        stop.store(true, Ordering::Relaxed);
+    });
+
+    assert_eq!(*gpio.lock().unwrap(), vec![0b01, 0b00, 0b11, 0b00, 0b01, 0b00]);
+}
@@ -0,0 +1,136 @@
+use std::thread;
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::{CardFamily, DetectedCard, ReaderError, ReaderType};
+use urniknet_terminal_lib::scan::{Scans, READER_BUSY_ERROR};
+use urniknet_terminal_lib::worker::{ScannedCard, Taps};
+
+fn scanned(uid: &[u8]) -> ScannedCard {
+    ScannedCard {
+        card: DetectedCard::from_uid(uid.to_vec(), CardFamily::Unknown, ReaderType::Simulated),
+        reader_name: String::from("entry"),
+    }
+}
+
+#[test]
+fn session_receives_the_offered_card() {
+    let scans = Scans::new();
+    let session = scans.start().unwrap();
+
+    assert!(scans.offer(&scanned(&[1, 2, 3, 4])));
+    let card = session.wait(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!(card.card.uid, vec![1, 2, 3, 4]);
+    assert_eq!(card.reader_name, "entry");
+}
+
+#[test]
+fn cards_are_not_taken_without_a_session() {
+    let scans = Scans::new();
+    assert!(!scans.offer(&scanned(&[1])));
+
+    drop(scans.start().unwrap());
+    assert!(!scans.offer(&scanned(&[1])));
+}
+
+#[test]
+fn second_scan_is_refused_while_one_is_open() {
+    let scans = Scans::new();
+    let first = scans.start().unwrap();
+
+    assert_eq!(scans.start().err().as_deref(), Some(READER_BUSY_ERROR));
+    drop(first);
+    assert!(scans.start().is_ok());
+}
+
+#[test]
+fn cancel_wakes_the_waiting_scan() {
+    let scans = Scans::new();
+    let session = scans.start().unwrap();
+    let id = session.id;
+
+    thread::scope(|scope| {
+        let waiter = scope.spawn(move || session.wait(Duration::from_secs(10)));
+        thread::sleep(Duration::from_millis(20));
+        assert!(scans.cancel(id));
+        assert!(waiter.join().unwrap().unwrap().is_none());
+    });
+    assert!(!scans.cancel(id));
+}
+
+#[test]
+fn cancelling_another_id_leaves_the_scan_open() {
+    let scans = Scans::new();
+    let session = scans.start().unwrap();
+
+    assert!(!scans.cancel(session.id + 1));
+    assert!(scans.offer(&scanned(&[9])));
+    assert!(session.wait(Duration::from_secs(1)).unwrap().is_some());
+}
+
+#[test]
+fn quiet_scan_times_out() {
+    let scans = Scans::new();
+    let session = scans.start().unwrap();
+
+    assert!(matches!(session.wait(Duration::from_millis(30)), Err(ReaderError::Timeout)));
+}
+
+#[test]
+fn sessions_get_distinct_ids() {
+    let scans = Scans::new();
+    let first = scans.start().unwrap().id;
+    let second = scans.start().unwrap().id;
+
+    assert_ne!(first, second);
+}
+
+#[test]
+fn session_takes_a_single_card() {
+    let scans = Scans::new();
+    let session = scans.start().unwrap();
+
+    assert!(scans.offer(&scanned(&[1, 2, 3, 4])));
+    assert!(!scans.offer(&scanned(&[5, 6, 7, 8])));
+    assert_eq!(session.wait(Duration::from_secs(1)).unwrap().unwrap().card.uid, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn card_offered_after_the_wait_timed_out_is_not_taken() {
+    let scans = Scans::new();
+    let session = scans.start().unwrap();
+
+    assert!(matches!(session.wait(Duration::from_millis(20)), Err(ReaderError::Timeout)));
+    // The session is still open until dropped, but may no longer take cards.
+    assert!(!scans.offer(&scanned(&[1, 2, 3, 4])));
+    assert!(!scans.cancel(session.id));
+}
+
+#[test]
+fn tap_is_punched_only_once() {
+    let taps = Taps::new(Duration::from_secs(30));
+    let first = taps.issue(scanned(&[1, 2, 3, 4]));
+    let second = taps.issue(scanned(&[5, 6, 7, 8]));
+
+    assert_ne!(first, second);
+    assert_eq!(taps.take(second).unwrap().card.uid, vec![5, 6, 7, 8]);
+    assert_eq!(taps.take(first).unwrap().card.uid, vec![1, 2, 3, 4]);
+    assert!(taps.take(first).is_none());
+}
+
+#[test]
+fn tap_lapses_after_the_window() {
+    let taps = Taps::new(Duration::from_millis(50));
+    let id = taps.issue(scanned(&[1, 2, 3, 4]));
+
+    thread::sleep(Duration::from_millis(80));
+    assert!(taps.take(id).is_none());
+}
+
+#[test]
+fn ids_that_were_never_issued_are_refused() {
+    let taps = Taps::new(Duration::from_secs(30));
+    taps.issue(scanned(&[1, 2, 3, 4]));
+
+    assert!(taps.take(0).is_none());
+    assert!(taps.take(42).is_none());
+}
@@ -0,0 +1,107 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::simulated::{Command, SimulatedReader};
+use urniknet_terminal_lib::reader::{Reader, ReaderError, ReaderType};
+
+/// A socket path of its own for every test, so they can run in parallel.
+fn socket_path(test: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("urniknet-sim-{}-{}.sock", std::process::id(), test))
+}
+
+/// Binds a simulator on a fresh socket and connects to it.
+fn connect(test: &str) -> (SimulatedReader, UnixStream) {
+    let path = socket_path(test);
+    let mut reader = SimulatedReader::new(&path);
+    reader.init().unwrap();
+    let client = UnixStream::connect(&path).unwrap();
+    (reader, client)
+}
+
+/// Sends `line` and waits for its reply, letting the reader pick the command up meanwhile.
+fn send(reader: &mut SimulatedReader, client: &UnixStream, line: &str) -> String {
+    writeln!(&*client, "{}", line).unwrap();
+    client.set_nonblocking(true).unwrap();
+    let mut replies = BufReader::new(client);
+    let mut reply = String::new();
+    for _ in 0..100 {
+        let _ = reader.card_present();
+        if replies.read_line(&mut reply).is_ok() && reply.ends_with('\n') {
+            break;
+        }
+    }
+    client.set_nonblocking(false).unwrap();
+    reply.trim().to_string()
+}
+
+#[test]
+fn parses_commands() {
+    assert_eq!(Command::parse("tap aa:bb:cc:01"), Ok(Command::Tap(vec![0xAA, 0xBB, 0xCC, 0x01])));
+    assert_eq!(Command::parse("place 04A1B2C3\n"), Ok(Command::Place(vec![0x04, 0xA1, 0xB2, 0xC3])));
+    assert_eq!(Command::parse("remove"), Ok(Command::Remove));
+    assert_eq!(Command::parse("timeout"), Ok(Command::Timeout));
+    assert_eq!(Command::parse("error unplugged"), Ok(Command::Error(String::from("unplugged"))));
+    assert!(Command::parse("tap xyz").is_err());
+    assert!(Command::parse("tap").is_err());
+    assert!(Command::parse("swipe aa").is_err());
+}
+
+#[test]
+fn tapped_card_is_read_once_and_lifted() {
+    let (mut reader, client) = connect("tap");
+
+    assert_eq!(send(&mut reader, &client, "tap aa:bb:cc:01"), "ok");
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+    assert_eq!(card.uid, vec![0xAA, 0xBB, 0xCC, 0x01]);
+    assert_eq!(card.reader_type, ReaderType::Simulated);
+    assert_eq!(reader.card_present().unwrap(), Some(false));
+    assert!(matches!(reader.read_uid(Duration::from_millis(50)), Err(ReaderError::Timeout)));
+}
+
+#[test]
+fn placed_card_rests_until_removed() {
+    let (mut reader, client) = connect("place");
+
+    send(&mut reader, &client, "place 01020304");
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), vec![1, 2, 3, 4]);
+    assert_eq!(reader.card_present().unwrap(), Some(true));
+    assert_eq!(send(&mut reader, &client, "remove"), "ok");
+    assert_eq!(reader.card_present().unwrap(), Some(false));
+}
+
+#[test]
+fn injects_timeouts_and_errors_in_order() {
+    let (mut reader, client) = connect("faults");
+
+    send(&mut reader, &client, "timeout");
+    send(&mut reader, &client, "error unplugged");
+    send(&mut reader, &client, "tap 0a0b");
+
+    assert!(matches!(reader.read_uid(Duration::from_secs(1)), Err(ReaderError::Timeout)));
+    match reader.read_uid(Duration::from_secs(1)) {
+        Err(ReaderError::Device(message)) => assert_eq!(message, "unplugged"),
+        other => panic!("expected a device error, got {:?}", other),
+    }
+    assert_eq!(reader.read_uid(Duration::from_secs(1)).unwrap(), vec![0x0A, 0x0B]);
+}
+
+#[test]
+fn rejects_unknown_commands() {
+    let (mut reader, client) = connect("unknown");
+
+    assert!(send(&mut reader, &client, "swipe aa").starts_with("error:"));
+}
+
+#[test]
+fn replaces_a_stale_socket_and_removes_it_on_drop() {
+    let path = socket_path("stale");
+    std::fs::write(&path, b"").unwrap();
+
+    let mut reader = SimulatedReader::new(&path);
+    reader.init().unwrap();
+    assert_eq!(reader.diagnostics().unwrap().transport.as_deref(), Some("unix"));
+    drop(reader);
+    assert!(!path.exists());
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use urniknet_terminal_lib::reader::wiegand::{DataLine, Edge, FrameAssembler, WiegandFrame, WiegandReader};
+use urniknet_terminal_lib::reader::{Reader, ReaderError, ReaderType, UidKind};
+
+/// Bits of `value`, most significant first.
+fn bits(value: u64, count: usize) -> Vec<bool> {
+    (0..count).rev().map(|bit| value >> bit & 1 == 1).collect()
+}
+
+/// Wraps data bits in the leading even and trailing odd parity bits.
+fn with_parity(data: Vec<bool>) -> Vec<bool> {
+    let half = data.len() / 2;
+    let ones = |bits: &[bool]| bits.iter().filter(|bit| **bit).count();
+    let mut frame = vec![ones(&data[..half]) % 2 == 1];
+    frame.extend_from_slice(&data);
+    frame.push(ones(&data[half..]) % 2 == 0);
+    frame
+}
+
+fn frame_26(facility: u8, card: u16) -> Vec<bool> {
+    with_parity(bits((facility as u64) << 16 | card as u64, 24))
+}
+
+fn frame_34(facility: u16, card: u16) -> Vec<bool> {
+    with_parity(bits((facility as u64) << 16 | card as u64, 32))
+}
+
+/// Edges of `frame` sent 2 ms apart, starting at `start_ns`.
+fn edges(frame: &[bool], start_ns: u64) -> Vec<Edge> {
+    frame
+        .iter()
+        .enumerate()
+        .map(|(index, bit)| Edge {
+            line: if *bit { DataLine::D1 } else { DataLine::D0 },
+            timestamp_ns: start_ns + index as u64 * 2_000_000,
+        })
+        .collect()
+}
+
+fn reader(edges: Vec<Edge>) -> WiegandReader {
+    WiegandReader::new(Box::new(VecDeque::from(edges)))
+}
+
+#[test]
+fn decodes_26_bit_frame() {
+    let frame = WiegandFrame::decode(&frame_26(123, 45678)).unwrap();
+
+    assert_eq!(frame.bit_count, 26);
+    assert_eq!(frame.facility, 123);
+    assert_eq!(frame.card_number, 45678);
+    assert_eq!(frame.uid(), vec![123, 0xB2, 0x6E]);
+}
+
+#[test]
+fn decodes_34_bit_frame() {
+    let frame = WiegandFrame::decode(&frame_34(4321, 65000)).unwrap();
+
+    assert_eq!(frame.bit_count, 34);
+    assert_eq!(frame.facility, 4321);
+    assert_eq!(frame.card_number, 65000);
+    assert_eq!(frame.uid(), vec![0x10, 0xE1, 0xFD, 0xE8]);
+}
+
+#[test]
+fn rejects_parity_errors() {
+    let mut frame = frame_26(1, 2);
+    frame[5] = !frame[5];
+    assert!(matches!(WiegandFrame::decode(&frame), Err(ReaderError::Protocol(_))));
+
+    let mut frame = frame_34(1, 2);
+    let last = frame.len() - 1;
+    frame[last] = !frame[last];
+    assert!(matches!(WiegandFrame::decode(&frame), Err(ReaderError::Protocol(_))));
+}
+
+#[test]
+fn rejects_unsupported_lengths() {
+    let error = WiegandFrame::decode(&[true; 30]).unwrap_err();
+    assert!(error.to_string().contains("30 bits"), "{}", error);
+}
+
+#[test]
+fn gap_between_edges_ends_the_frame() {
+    let mut assembler = FrameAssembler::new();
+    let first = edges(&frame_26(1, 1), 0);
+    let second = edges(&frame_26(2, 2), 200_000_000);
+
+    for edge in &first {
+        assert_eq!(assembler.push(*edge), None);
+    }
+    assert_eq!(assembler.push(second[0]), Some(frame_26(1, 1)));
+    for edge in &second[1..] {
+        assert_eq!(assembler.push(*edge), None);
+    }
+    assert_eq!(assembler.finish(), Some(frame_26(2, 2)));
+    assert_eq!(assembler.finish(), None);
+}
+
+#[test]
+fn reader_returns_cards_from_simulated_edges() {
+    let mut script = edges(&frame_26(10, 1000), 0);
+    script.extend(edges(&frame_34(300, 2000), 500_000_000));
+    let mut reader = reader(script);
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+    assert_eq!(card.uid, vec![10, 0x03, 0xE8]);
+    assert_eq!(card.reader_type, ReaderType::Wiegand);
+    assert_eq!(reader.read_frame(Duration::from_secs(1)).unwrap().card_number, 2000);
+}
+
+#[test]
+fn facility_codes_from_2048_are_not_random_uids() {
+    let mut reader = reader(edges(&frame_34(2048, 1000), 0));
+
+    let card = reader.read_card(Duration::from_secs(1)).unwrap();
+    assert_eq!(card.uid, vec![0x08, 0x00, 0x03, 0xE8]);
+    assert_eq!(card.uid_kind, UidKind::Unique);
+}
+
+#[test]
+fn truncated_frame_is_a_protocol_error() {
+    let frame = frame_26(10, 1000);
+    let mut reader = reader(edges(&frame[..20], 0));
+
+    assert!(matches!(reader.read_uid(Duration::from_secs(1)), Err(ReaderError::Protocol(_))));
+}
+
+#[test]
+fn quiet_lines_time_out() {
+    let mut reader = reader(Vec::new());
+
+    assert!(matches!(reader.read_uid(Duration::from_millis(60)), Err(ReaderError::Timeout)));
+}